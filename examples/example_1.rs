@@ -135,9 +135,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             // Try to write the replay back to verify our packer works
             println!("\n=== Testing Write Functionality ===");
             let output_path = "assets/test_output.osr";
-            match replay.write_path(output_path) {
-                Ok(()) => {
-                    println!("Successfully wrote replay to: {}", output_path);
+            match replay.write_path_counted(output_path) {
+                Ok(bytes_written) => {
+                    println!(
+                        "Successfully wrote {} bytes to: {}",
+                        bytes_written, output_path
+                    );
 
                     // Verify by reading it back
                     match Replay::from_path(output_path) {
@@ -0,0 +1,1030 @@
+//! Frame analysis helpers for replays.
+//!
+//! This module contains methods on [`Replay`] that inspect the replay frames
+//! rather than the replay metadata, along with the small types they return.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    replay::Replay,
+    types::{GameMode, Key, KeyMania, Mod, ReplayEvent, ReplayEventMania},
+};
+
+/// Summary statistics over the `time_delta` values of a replay's frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeltaStats {
+    /// The smallest frame delta, in milliseconds
+    pub min: i32,
+    /// The largest frame delta, in milliseconds
+    pub max: i32,
+    /// The mean frame delta, in milliseconds
+    pub mean: f64,
+    /// The median frame delta, in milliseconds
+    pub median: f64,
+    /// The most common frame delta, in milliseconds
+    pub mode_ms: i32,
+}
+
+/// A mode-independent view of a single frame at an absolute time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlatFrame {
+    /// The absolute time of this frame, in milliseconds
+    pub time: i64,
+    /// The x position of the cursor or catcher, or 0 if the mode has none
+    pub x: f32,
+    /// The y position of the cursor, or 0 outside of osu!standard
+    pub y: f32,
+    /// The raw key bits of this frame, as returned by [`ReplayEvent::key_bits`]
+    pub keys: u32,
+}
+
+/// A continuous press of a single osu!mania lane.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManiaLaneEvent {
+    /// The zero-based lane index
+    pub lane: u8,
+    /// The absolute time the lane was pressed, in milliseconds
+    pub start: i64,
+    /// The absolute time the lane was released, in milliseconds
+    pub end: i64,
+}
+
+impl ManiaLaneEvent {
+    /// Returns how long the lane was held, in milliseconds.
+    pub fn duration(&self) -> i64 {
+        self.end - self.start
+    }
+}
+
+impl FlatFrame {
+    fn from_event(time: i64, event: &ReplayEvent) -> Self {
+        let (x, y) = match event {
+            ReplayEvent::Osu(event) => (event.x, event.y),
+            ReplayEvent::Catch(event) => (event.x, 0.0),
+            ReplayEvent::Taiko(_) | ReplayEvent::Mania(_) => (0.0, 0.0),
+        };
+
+        Self {
+            time,
+            x,
+            y,
+            keys: event.key_bits(),
+        }
+    }
+}
+
+impl Replay {
+    /// Computes statistics over the frame deltas of this replay.
+    ///
+    /// Leading frames with a negative `time_delta` (osu! writes these before
+    /// gameplay starts) are excluded. Most legitimate replays sample at roughly
+    /// 16ms, so unusual statistics can hint at a modified replay.
+    ///
+    /// # Returns
+    ///
+    /// The delta statistics, or `None` if there are no frames to measure
+    pub fn frame_delta_stats(&self) -> Option<DeltaStats> {
+        let mut deltas: Vec<i32> = self
+            .replay_data
+            .iter()
+            .map(|event| event.time_delta())
+            .skip_while(|&delta| delta < 0)
+            .collect();
+
+        if deltas.is_empty() {
+            return None;
+        }
+
+        deltas.sort_unstable();
+
+        let len = deltas.len();
+        let mean = deltas.iter().map(|&d| d as f64).sum::<f64>() / len as f64;
+        let median = if len.is_multiple_of(2) {
+            (deltas[len / 2 - 1] as f64 + deltas[len / 2] as f64) / 2.0
+        } else {
+            deltas[len / 2] as f64
+        };
+
+        // The deltas are sorted, so equal values form contiguous runs
+        let mut mode_ms = deltas[0];
+        let mut best_run = 0;
+        let mut run_start = 0;
+        for i in 1..=len {
+            if i == len || deltas[i] != deltas[run_start] {
+                if i - run_start > best_run {
+                    best_run = i - run_start;
+                    mode_ms = deltas[run_start];
+                }
+                run_start = i;
+            }
+        }
+
+        Some(DeltaStats {
+            min: deltas[0],
+            max: deltas[len - 1],
+            mean,
+            median,
+            mode_ms,
+        })
+    }
+
+    /// Scores how human-plausible the frame timing of this replay is.
+    ///
+    /// Three checks each contribute a penalty from 0 to 1, weighted as follows:
+    ///
+    /// * 0.4 for too-regular deltas: real replays jitter around the frame
+    ///   rate, so the penalty grows as the most common delta covers more than
+    ///   90% of the frames
+    /// * 0.3 for negative deltas after gameplay starts: the penalty reaches 1
+    ///   once 1% of the frames go back in time
+    /// * 0.3 for improbable input rates: the penalty grows as the busiest
+    ///   one-second window goes from 20 to 40 key presses
+    ///
+    /// This is a coarse aggregate of several anomaly checks and not proof of
+    /// tampering on its own.
+    ///
+    /// # Returns
+    ///
+    /// A score from 0.0 (implausible) to 1.0 (plausible); 1.0 if there are no
+    /// frames to measure
+    pub fn timing_integrity(&self) -> f64 {
+        const REGULARITY_WEIGHT: f64 = 0.4;
+        const NEGATIVE_WEIGHT: f64 = 0.3;
+        const INPUT_RATE_WEIGHT: f64 = 0.3;
+
+        let deltas: Vec<i32> = self
+            .replay_data
+            .iter()
+            .map(|event| event.time_delta())
+            .skip_while(|&delta| delta < 0)
+            .collect();
+        let Some(stats) = self.frame_delta_stats() else {
+            return 1.0;
+        };
+
+        let mode_share = deltas
+            .iter()
+            .filter(|&&delta| delta == stats.mode_ms)
+            .count() as f64
+            / deltas.len() as f64;
+        let regularity_penalty = ((mode_share - 0.9) / 0.1).clamp(0.0, 1.0);
+
+        let negative_share =
+            deltas.iter().filter(|&&delta| delta < 0).count() as f64 / deltas.len() as f64;
+        let negative_penalty = (negative_share / 0.01).min(1.0);
+
+        let mut onsets = Vec::new();
+        let mut time = 0i64;
+        let mut previous = 0u32;
+        for event in &self.replay_data {
+            time += event.time_delta() as i64;
+            let keys = event.key_bits();
+            for _ in 0..(keys & !previous).count_ones() {
+                onsets.push(time);
+            }
+            previous = keys;
+        }
+        onsets.sort_unstable();
+
+        let mut max_rate = 0;
+        let mut window_start = 0;
+        for (i, &onset) in onsets.iter().enumerate() {
+            while onset - onsets[window_start] >= 1000 {
+                window_start += 1;
+            }
+            max_rate = max_rate.max(i - window_start + 1);
+        }
+        let input_rate_penalty = ((max_rate as f64 - 20.0) / 20.0).clamp(0.0, 1.0);
+
+        1.0 - (REGULARITY_WEIGHT * regularity_penalty
+            + NEGATIVE_WEIGHT * negative_penalty
+            + INPUT_RATE_WEIGHT * input_rate_penalty)
+    }
+
+    /// Checks whether the frame timing looks uniformly compressed by a timewarp.
+    ///
+    /// Frame deltas are on the beatmap's clock, so the expected interval is
+    /// scaled by [`Mod::speed_multiplier`](crate::Mod::speed_multiplier): a
+    /// 60 fps client records roughly 25ms deltas with Double Time. A timewarp
+    /// slows the game clock down, which shrinks every delta, so the median
+    /// delta falls below the expected interval. This is a heuristic and not
+    /// proof of cheating on its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_frame_ms` - The client frame interval without speed mods,
+    ///   such as 16.67 for 60 fps
+    /// * `tolerance` - How far below the expected interval the median may be,
+    ///   as a fraction of it (0.1 allows 10%)
+    ///
+    /// # Returns
+    ///
+    /// Whether the median delta is below the tolerated interval; false if
+    /// there are no frames to measure
+    pub fn detect_timewarp(&self, expected_frame_ms: f64, tolerance: f64) -> bool {
+        let Some(stats) = self.frame_delta_stats() else {
+            return false;
+        };
+
+        let expected = expected_frame_ms * self.mods.speed_multiplier();
+        stats.median < expected * (1.0 - tolerance)
+    }
+
+    /// Estimates the rate at which the client recorded frames, in Hz.
+    ///
+    /// The rate is derived from the median frame delta. Deltas are on the
+    /// beatmap's clock, so they are divided by
+    /// [`Mod::speed_multiplier`](crate::Mod::speed_multiplier) first to get the
+    /// real capture rate: a 60 fps client reads as 60 Hz with or without
+    /// Double Time.
+    ///
+    /// # Returns
+    ///
+    /// The estimated frame rate, or `None` if there are fewer than two frames
+    /// to measure or the median delta is not positive
+    pub fn estimated_frame_rate(&self) -> Option<f64> {
+        if self.replay_data.len() < 2 {
+            return None;
+        }
+
+        let median = self.frame_delta_stats()?.median / self.mods.speed_multiplier();
+        (median > 0.0).then(|| 1000.0 / median)
+    }
+
+    /// Converts the frames of this replay into an N×4 array for numeric pipelines.
+    ///
+    /// Each row holds `[abs_time, x, y, keys]` for one frame. The layout is
+    /// designed for osu!standard replays; for other modes the columns a frame
+    /// doesn't have (such as `y` outside of osu!standard) are filled with zero.
+    ///
+    /// # Returns
+    ///
+    /// An array with one row per frame
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> ndarray::Array2<f32> {
+        let mut array = ndarray::Array2::zeros((self.replay_data.len(), 4));
+        let mut time = 0i64;
+
+        for (row, event) in self.replay_data.iter().enumerate() {
+            time += event.time_delta() as i64;
+            let (x, y, keys) = match event {
+                ReplayEvent::Osu(event) => (event.x, event.y, event.keys.value()),
+                ReplayEvent::Taiko(event) => (event.x as f32, 0.0, event.keys.value()),
+                ReplayEvent::Catch(event) => (event.x, 0.0, event.dashing as u32),
+                ReplayEvent::Mania(event) => (0.0, 0.0, event.keys.value()),
+            };
+
+            array[[row, 0]] = time as f32;
+            array[[row, 1]] = x;
+            array[[row, 2]] = y;
+            array[[row, 3]] = keys as f32;
+        }
+
+        array
+    }
+
+    /// Returns the raw key bits of every frame, as returned by [`ReplayEvent::key_bits`].
+    ///
+    /// Together with [`Replay::time_series`] this gives a columnar view of
+    /// the frames for numeric processing.
+    pub fn key_series(&self) -> Vec<u32> {
+        self.replay_data.iter().map(ReplayEvent::key_bits).collect()
+    }
+
+    /// Returns the absolute time of every frame, in milliseconds.
+    pub fn time_series(&self) -> Vec<i64> {
+        let mut time = 0i64;
+        self.replay_data
+            .iter()
+            .map(|event| {
+                time += event.time_delta() as i64;
+                time
+            })
+            .collect()
+    }
+
+    /// Counts the distinct presses of each key bit across the replay.
+    ///
+    /// A press is an off→on transition of a single key bit between
+    /// consecutive frames, so holding a key over many frames counts once.
+    ///
+    /// # Returns
+    ///
+    /// A map from each single key bit to the number of times it was pressed
+    pub fn press_count_per_key(&self) -> HashMap<u32, u32> {
+        let mut counts = HashMap::new();
+        let mut previous = 0u32;
+
+        for event in &self.replay_data {
+            let keys = event.key_bits();
+            let mut onsets = keys & !previous;
+
+            while onsets != 0 {
+                let bit = onsets & onsets.wrapping_neg();
+                *counts.entry(bit).or_insert(0) += 1;
+                onsets &= !bit;
+            }
+
+            previous = keys;
+        }
+
+        counts
+    }
+
+    /// Partitions the frames of this replay by the beatmap objects they lead into.
+    ///
+    /// Replay frame times are already on the beatmap's clock (speed mods change
+    /// the frame spacing, not the timeline), so they are compared directly to
+    /// `object_times`.
+    ///
+    /// # Arguments
+    ///
+    /// * `object_times` - The start times of the beatmap objects, in ascending order
+    ///
+    /// # Returns
+    ///
+    /// `object_times.len() + 1` groups: frames before the first object, then the
+    /// frames from each object up to (but excluding) the next one, with the last
+    /// group holding the frames from the final object onwards
+    pub fn frames_grouped_by_object(&self, object_times: &[i32]) -> Vec<Vec<&ReplayEvent>> {
+        let mut groups = vec![Vec::new(); object_times.len() + 1];
+        let mut time = 0i64;
+
+        for event in &self.replay_data {
+            time += event.time_delta() as i64;
+            let group = object_times.partition_point(|&object_time| object_time as i64 <= time);
+            groups[group].push(event);
+        }
+
+        groups
+    }
+
+    /// Labels which beatmap objects had a key press near them.
+    ///
+    /// A press is any key bit going from released to pressed. As with
+    /// [`Replay::frames_grouped_by_object`], frame times are already on the
+    /// beatmap's clock, so speed mods need no adjustment of `object_times`.
+    ///
+    /// # Arguments
+    ///
+    /// * `object_times` - The start times of the beatmap objects
+    /// * `window_ms` - How far before or after an object a press may be, in milliseconds
+    ///
+    /// # Returns
+    ///
+    /// One entry per object, true if a press happened within `window_ms` of it
+    pub fn keys_during_object(&self, object_times: &[i32], window_ms: i32) -> Vec<bool> {
+        let mut onsets = Vec::new();
+        let mut time = 0i64;
+        let mut previous = 0u32;
+
+        for event in &self.replay_data {
+            time += event.time_delta() as i64;
+            let keys = event.key_bits();
+            if keys & !previous != 0 {
+                onsets.push(time);
+            }
+            previous = keys;
+        }
+        onsets.sort_unstable();
+
+        let window = window_ms.unsigned_abs() as i64;
+        object_times
+            .iter()
+            .map(|&object_time| {
+                let start = object_time as i64 - window;
+                let first = onsets.partition_point(|&onset| onset < start);
+                onsets
+                    .get(first)
+                    .is_some_and(|&onset| onset <= object_time as i64 + window)
+            })
+            .collect()
+    }
+
+    /// Resamples the frames of this replay to a fixed tick rate.
+    ///
+    /// Ticks are spaced evenly from the first frame to the last. Positions are
+    /// linearly interpolated between the surrounding frames (osu!standard and
+    /// osu!catch), while key state is held forward from the latest frame at or
+    /// before each tick and is never interpolated.
+    ///
+    /// # Arguments
+    ///
+    /// * `hz` - The number of ticks per second
+    ///
+    /// # Returns
+    ///
+    /// One frame per tick, or an empty list if `hz` is 0 or there are no frames
+    pub fn resample_inputs(&self, hz: u32) -> Vec<FlatFrame> {
+        let frames = self.flat_frames();
+
+        let (Some(first), Some(last)) = (frames.first(), frames.last()) else {
+            return Vec::new();
+        };
+        if hz == 0 {
+            return Vec::new();
+        }
+
+        let interval = 1000.0 / hz as f64;
+        let tick_count = ((last.time - first.time).max(0) as f64 / interval) as usize + 1;
+
+        sample_frames(&frames, first.time as f64, interval, tick_count)
+    }
+
+    /// Subsamples the osu!standard cursor path for lightweight previews.
+    ///
+    /// Positions are picked at evenly spaced frame indices, always keeping the
+    /// first and last positions, so a thumbnail can be drawn without every frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_points` - The maximum number of positions to return
+    ///
+    /// # Returns
+    ///
+    /// At most `max_points` cursor positions in frame order; empty for other modes
+    pub fn preview_path(&self, max_points: usize) -> Vec<(f32, f32)> {
+        let positions: Vec<(f32, f32)> = self
+            .replay_data
+            .iter()
+            .filter_map(|event| match event {
+                ReplayEvent::Osu(event) => Some((event.x, event.y)),
+                _ => None,
+            })
+            .collect();
+
+        if positions.len() <= max_points {
+            return positions;
+        }
+        if max_points <= 1 {
+            return positions.into_iter().take(max_points).collect();
+        }
+
+        let last = positions.len() - 1;
+        (0..max_points)
+            .map(|i| positions[i * last / (max_points - 1)])
+            .collect()
+    }
+
+    /// Returns the bounding box of the osu!standard cursor over all frames.
+    ///
+    /// Positions outside of 0..512 × 0..384 mean the cursor left the playfield,
+    /// and the box can be used to frame a render of the play.
+    ///
+    /// # Returns
+    ///
+    /// `(min_x, min_y, max_x, max_y)`, or `None` for other modes or a replay without frames
+    pub fn cursor_bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        self.replay_data
+            .iter()
+            .filter_map(|event| match event {
+                ReplayEvent::Osu(event) => Some((event.x, event.y)),
+                _ => None,
+            })
+            .fold(None, |bounds, (x, y)| match bounds {
+                None => Some((x, y, x, y)),
+                Some((min_x, min_y, max_x, max_y)) => {
+                    Some((min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)))
+                }
+            })
+    }
+
+    /// Returns the osu!catch catcher position over time.
+    ///
+    /// # Returns
+    ///
+    /// The absolute time and x position of the catcher at each frame; empty
+    /// for other modes
+    pub fn catcher_path(&self) -> Vec<(i64, f32)> {
+        if self.mode != GameMode::Catch {
+            return Vec::new();
+        }
+
+        let mut time = 0i64;
+        self.replay_data
+            .iter()
+            .filter_map(|event| {
+                time += event.time_delta() as i64;
+                match event {
+                    ReplayEvent::Catch(event) => Some((time, event.x)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the total horizontal distance the osu!catch catcher moved.
+    ///
+    /// # Returns
+    ///
+    /// The sum of the absolute x movements between frames in osu!pixels; 0.0
+    /// for other modes
+    pub fn catch_distance_traveled(&self) -> f64 {
+        self.catcher_path()
+            .windows(2)
+            .map(|pair| (pair[1].1 as f64 - pair[0].1 as f64).abs())
+            .sum()
+    }
+
+    /// Measures how closely the inputs of this replay match another replay's.
+    ///
+    /// Both replays are resampled at 100 Hz over the union of their time spans.
+    /// A tick matches when both replays hold the same keys and, in osu!standard,
+    /// the cursors are within 2 osu!pixels of each other. A suspiciously high
+    /// similarity between plays by different players can indicate a copied replay.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The replay to compare against
+    ///
+    /// # Returns
+    ///
+    /// The fraction of matching ticks, from 0.0 to 1.0; 0.0 if the modes differ
+    /// or either replay has no frames
+    pub fn input_similarity(&self, other: &Replay) -> f64 {
+        const TICK_INTERVAL_MS: f64 = 10.0;
+        const POSITION_EPSILON: f32 = 2.0;
+
+        if self.mode != other.mode {
+            return 0.0;
+        }
+
+        let frames = self.flat_frames();
+        let other_frames = other.flat_frames();
+        let (Some(first), Some(last), Some(other_first), Some(other_last)) = (
+            frames.first(),
+            frames.last(),
+            other_frames.first(),
+            other_frames.last(),
+        ) else {
+            return 0.0;
+        };
+
+        let start = first.time.min(other_first.time);
+        let end = last.time.max(other_last.time);
+        let tick_count = ((end - start).max(0) as f64 / TICK_INTERVAL_MS) as usize + 1;
+
+        let samples = sample_frames(&frames, start as f64, TICK_INTERVAL_MS, tick_count);
+        let other_samples =
+            sample_frames(&other_frames, start as f64, TICK_INTERVAL_MS, tick_count);
+
+        let matching = samples
+            .iter()
+            .zip(&other_samples)
+            .filter(|(a, b)| {
+                a.keys == b.keys
+                    && (self.mode != GameMode::Std
+                        || (a.x - b.x).hypot(a.y - b.y) <= POSITION_EPSILON)
+            })
+            .count();
+
+        matching as f64 / tick_count as f64
+    }
+
+    pub(crate) fn flat_frames(&self) -> Vec<FlatFrame> {
+        let mut time = 0i64;
+        self.replay_data
+            .iter()
+            .map(|event| {
+                time += event.time_delta() as i64;
+                FlatFrame::from_event(time, event)
+            })
+            .collect()
+    }
+
+    /// Samples the key-press density of this replay around a point in time.
+    ///
+    /// Every key bit going from released to pressed counts as one onset, so
+    /// pressing two keys on the same frame counts twice.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_ms` - The absolute time to sample at
+    /// * `window_ms` - The width of the window centered on `time_ms`
+    ///
+    /// # Returns
+    ///
+    /// The number of onsets in the window per second, or 0.0 for an empty window
+    pub fn input_density_at(&self, time_ms: i64, window_ms: i64) -> f64 {
+        if window_ms <= 0 {
+            return 0.0;
+        }
+
+        let start = time_ms - window_ms / 2;
+        let end = time_ms + window_ms / 2;
+        let mut time = 0i64;
+        let mut previous = 0u32;
+        let mut onsets = 0u32;
+
+        for event in &self.replay_data {
+            time += event.time_delta() as i64;
+            let keys = event.key_bits();
+            if (start..=end).contains(&time) {
+                onsets += (keys & !previous).count_ones();
+            }
+            previous = keys;
+        }
+
+        onsets as f64 / (window_ms as f64 / 1000.0)
+    }
+
+    /// Counts the osu!standard presses made with mouse buttons and with keyboard keys.
+    ///
+    /// osu! sets the matching mouse bit alongside a keyboard key (K1 implies M1,
+    /// K2 implies M2), so a newly pressed M1/M2 bit only counts as a mouse press
+    /// when the corresponding K1/K2 bit is not held on that frame.
+    ///
+    /// # Returns
+    ///
+    /// `(mouse_presses, keyboard_presses)`, both 0 for other game modes
+    pub fn input_device_breakdown(&self) -> (u32, u32) {
+        let mut mouse_presses = 0;
+        let mut keyboard_presses = 0;
+        let mut previous = 0u32;
+
+        for event in &self.replay_data {
+            let ReplayEvent::Osu(event) = event else {
+                continue;
+            };
+
+            let keys = event.keys.value();
+            let onsets = keys & !previous;
+
+            let mut mouse_onsets = onsets & (Key::M1.value() | Key::M2.value());
+            if keys & Key::K1.value() != 0 {
+                mouse_onsets &= !Key::M1.value();
+            }
+            if keys & Key::K2.value() != 0 {
+                mouse_onsets &= !Key::M2.value();
+            }
+
+            mouse_presses += mouse_onsets.count_ones();
+            keyboard_presses += (onsets & (Key::K1.value() | Key::K2.value())).count_ones();
+            previous = keys;
+        }
+
+        (mouse_presses, keyboard_presses)
+    }
+
+    /// Estimates the highest combo an osu!taiko replay's inputs could have reached.
+    ///
+    /// Every note needs at least one don or kat press, so the total number of
+    /// presses is a rough ceiling on the combo. This is an estimate only: it
+    /// overcounts presses spent on drumrolls and swells, so it can only point
+    /// out a stored combo that is too high, never one that is too low.
+    ///
+    /// # Returns
+    ///
+    /// The number of don and kat presses (saturating at `u16::MAX`), or `None`
+    /// for other game modes
+    pub fn estimate_max_combo_taiko(&self) -> Option<u16> {
+        if self.mode != GameMode::Taiko {
+            return None;
+        }
+
+        let mut presses = 0u32;
+        let mut previous = 0u32;
+        for event in &self.replay_data {
+            let ReplayEvent::Taiko(event) = event else {
+                continue;
+            };
+
+            let keys = event.keys.value();
+            presses += (keys & !previous).count_ones();
+            previous = keys;
+        }
+
+        Some(presses.min(u16::MAX as u32) as u16)
+    }
+
+    /// Returns whether the stored `max_combo` exceeds what the inputs allow.
+    ///
+    /// This compares `max_combo` to [`Replay::estimate_max_combo_taiko`], so it
+    /// is always `false` outside of osu!taiko. A mismatch hints at edited
+    /// metadata or frames.
+    pub fn max_combo_exceeds_estimate(&self) -> bool {
+        self.estimate_max_combo_taiko()
+            .is_some_and(|estimate| self.max_combo > estimate)
+    }
+
+    /// Returns the absolute time of the first frame with a key held.
+    ///
+    /// osu! starts replays with setup frames that hold no keys, often with a
+    /// negative or very large delta for skipped intros. Their deltas are still
+    /// summed, so the result is on the same clock as the beatmap and can be
+    /// used to align the replay with the audio.
+    ///
+    /// # Returns
+    ///
+    /// The time of the first key-bearing frame in milliseconds, or 0 if no
+    /// frame holds a key
+    pub fn gameplay_start_offset_ms(&self) -> i64 {
+        let mut time = 0i64;
+
+        for event in &self.replay_data {
+            time += event.time_delta() as i64;
+            if event.key_bits() != 0 {
+                return time;
+            }
+        }
+
+        0
+    }
+
+    /// Returns the frames of this replay with their times divided by the mod speed.
+    ///
+    /// Frame times are stored on the beatmap's clock; dividing them by
+    /// [`Mod::speed_multiplier`](crate::Mod::speed_multiplier) removes the
+    /// Double Time or Half Time rate, so a viewer can step through the frames
+    /// at the pace they were actually played. Without a speed mod, times are
+    /// unchanged.
+    ///
+    /// # Returns
+    ///
+    /// Each frame paired with its rate-adjusted absolute time, in milliseconds
+    /// rounded down
+    pub fn events_at_native_rate(&self) -> Vec<(i64, ReplayEvent)> {
+        let multiplier = self.mods.speed_multiplier();
+        let mut time = 0i64;
+
+        self.replay_data
+            .iter()
+            .map(|event| {
+                time += event.time_delta() as i64;
+                ((time as f64 / multiplier).floor() as i64, event.clone())
+            })
+            .collect()
+    }
+
+    /// Splits a co-op osu!mania replay into the frames of each player.
+    ///
+    /// In co-op the first player's lanes take the low key bits and the second
+    /// player's lanes the bits right above them, see [`KeyMania`](crate::KeyMania).
+    /// Both streams keep every frame with its original delta.
+    ///
+    /// # Arguments
+    ///
+    /// * `per_player_keys` - The number of lanes each player has
+    ///
+    /// # Returns
+    ///
+    /// The frames of the first and second player, with lanes starting at K1
+    /// for both, or `None` if this isn't a co-op osu!mania replay
+    pub fn split_coop(&self, per_player_keys: u8) -> Option<(Vec<ReplayEvent>, Vec<ReplayEvent>)> {
+        if self.mode != GameMode::Mania || !self.mods.contains(Mod::KEY_COOP) {
+            return None;
+        }
+
+        let shift = per_player_keys as u32;
+        let mask = 1u32.checked_shl(shift).map_or(u32::MAX, |bit| bit - 1);
+        let player = |keys: u32, time_delta: i32| {
+            ReplayEvent::Mania(ReplayEventMania {
+                time_delta,
+                keys: KeyMania(keys & mask),
+            })
+        };
+
+        Some(
+            self.replay_data
+                .iter()
+                .filter_map(|event| match event {
+                    ReplayEvent::Mania(event) => {
+                        let keys = event.keys.value();
+                        let high = keys.checked_shr(shift).unwrap_or(0);
+                        Some((
+                            player(keys, event.time_delta),
+                            player(high, event.time_delta),
+                        ))
+                    }
+                    _ => None,
+                })
+                .unzip(),
+        )
+    }
+
+    /// Finds the gaps in the frame stream left by in-game pauses.
+    ///
+    /// osu! stops recording frames while the game is paused, so a pause shows
+    /// up as a single large time delta between two consecutive frames. Gaps
+    /// up to the first frame with a key held, see
+    /// [`Replay::gameplay_start_offset_ms`], are skipped intros and setup
+    /// frames rather than pauses, and aren't reported. Without any key press
+    /// only the delta of the first frame is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_gap_ms` - The time between consecutive frames above which a gap is reported
+    ///
+    /// # Returns
+    ///
+    /// The absolute `(start, end)` times of each gap
+    pub fn detect_pauses(&self, min_gap_ms: i64) -> Vec<(i64, i64)> {
+        let first_press = self
+            .replay_data
+            .iter()
+            .position(|event| event.key_bits() != 0)
+            .unwrap_or(0);
+        let mut pauses = Vec::new();
+        let mut time = 0i64;
+
+        for (i, event) in self.replay_data.iter().enumerate() {
+            let delta = event.time_delta() as i64;
+            if i > first_press && delta > min_gap_ms {
+                pauses.push((time, time + delta));
+            }
+            time += delta;
+        }
+
+        pauses
+    }
+
+    /// Tracks the presses of each osu!mania lane across the replay.
+    ///
+    /// A press starts on the frame a lane bit turns on and ends on the frame
+    /// it turns off. Lanes still held on the last frame are closed there.
+    /// Bits for lanes at or above `key_count` are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_count` - The number of lanes of the beatmap
+    ///
+    /// # Returns
+    ///
+    /// One event per press, ordered by start time and then by lane
+    pub fn mania_lane_events(&self, key_count: u8) -> Vec<ManiaLaneEvent> {
+        let lane_count = key_count.min(18);
+        let mut pressed_at: Vec<Option<i64>> = vec![None; lane_count as usize];
+        let mut lane_events = Vec::new();
+        let mut time = 0i64;
+
+        for event in &self.replay_data {
+            time += event.time_delta() as i64;
+            let ReplayEvent::Mania(event) = event else {
+                continue;
+            };
+
+            for lane in 0..lane_count {
+                let held = event.keys.value() & (1 << lane) != 0;
+                match (pressed_at[lane as usize], held) {
+                    (None, true) => pressed_at[lane as usize] = Some(time),
+                    (Some(start), false) => {
+                        lane_events.push(ManiaLaneEvent {
+                            lane,
+                            start,
+                            end: time,
+                        });
+                        pressed_at[lane as usize] = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for (lane, start) in pressed_at.into_iter().enumerate() {
+            if let Some(start) = start {
+                lane_events.push(ManiaLaneEvent {
+                    lane: lane as u8,
+                    start,
+                    end: time,
+                });
+            }
+        }
+
+        lane_events.sort_by_key(|event| (event.start, event.lane));
+        lane_events
+    }
+
+    /// Returns the osu!mania presses held long enough to be long notes.
+    ///
+    /// This filters [`Replay::mania_lane_events`] to tell holds apart from taps.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_count` - The number of lanes of the beatmap
+    /// * `min_hold_ms` - The duration a press must exceed to count as a hold
+    ///
+    /// # Returns
+    ///
+    /// The held presses, ordered by start time and then by lane
+    pub fn mania_hold_notes(&self, key_count: u8, min_hold_ms: i64) -> Vec<ManiaLaneEvent> {
+        self.mania_lane_events(key_count)
+            .into_iter()
+            .filter(|event| event.duration() > min_hold_ms)
+            .collect()
+    }
+
+    /// Hashes the frames of this replay, ignoring all metadata.
+    ///
+    /// Two replays with the same frames hash equal regardless of their
+    /// username, score or other metadata, which helps detect duplicated
+    /// inputs. The hash is stable across runs and platforms.
+    ///
+    /// # Returns
+    ///
+    /// The first 8 bytes of an MD5 digest over every frame's mode, time
+    /// delta, position and keys
+    pub fn frames_content_hash(&self) -> u64 {
+        let mut context = md5::Context::new();
+
+        for event in &self.replay_data {
+            let (mode, x, y) = match event {
+                ReplayEvent::Osu(event) => (0u8, event.x.to_bits(), event.y.to_bits()),
+                ReplayEvent::Taiko(event) => (1, event.x as u32, 0),
+                ReplayEvent::Catch(event) => (2, event.x.to_bits(), 0),
+                ReplayEvent::Mania(_) => (3, 0, 0),
+            };
+
+            context.consume([mode]);
+            context.consume(event.time_delta().to_le_bytes());
+            context.consume(x.to_le_bytes());
+            context.consume(y.to_le_bytes());
+            context.consume(event.key_bits().to_le_bytes());
+        }
+
+        let digest = context.finalize();
+        u64::from_le_bytes(digest.0[..8].try_into().expect("digest is 16 bytes"))
+    }
+
+    /// Returns the frames of this replay in reverse temporal order.
+    ///
+    /// Each frame keeps its position and keys; only the order and the time
+    /// deltas change, so that playing the result back runs the replay
+    /// backwards over the same total duration.
+    ///
+    /// # Returns
+    ///
+    /// The reversed frames, with recomputed time deltas
+    pub fn reversed_frames(&self) -> Vec<ReplayEvent> {
+        let mut time = 0i64;
+        let times: Vec<i64> = self
+            .replay_data
+            .iter()
+            .map(|event| {
+                time += event.time_delta() as i64;
+                time
+            })
+            .collect();
+
+        let (Some(&first), Some(&last)) = (times.first(), times.last()) else {
+            return Vec::new();
+        };
+
+        let mut previous = 0i64;
+        self.replay_data
+            .iter()
+            .zip(&times)
+            .rev()
+            .map(|(event, &time)| {
+                let reversed_time = last - time + first;
+                let mut event = event.clone();
+                event.set_time_delta((reversed_time - previous) as i32);
+                previous = reversed_time;
+                event
+            })
+            .collect()
+    }
+}
+
+/// Samples `frames` at `tick_count` evenly spaced ticks starting at `start`.
+///
+/// Positions are interpolated between the surrounding frames and keys are held
+/// forward. Ticks outside the frames' time span take the nearest frame's state.
+fn sample_frames(
+    frames: &[FlatFrame],
+    start: f64,
+    interval: f64,
+    tick_count: usize,
+) -> Vec<FlatFrame> {
+    let mut resampled = Vec::with_capacity(tick_count);
+    let mut current = 0;
+
+    for tick in 0..tick_count {
+        let tick_time = start + tick as f64 * interval;
+        while current + 1 < frames.len() && frames[current + 1].time as f64 <= tick_time {
+            current += 1;
+        }
+
+        let frame = &frames[current];
+        let (x, y) = match frames.get(current + 1) {
+            Some(next) if next.time > frame.time => {
+                let t = ((tick_time - frame.time as f64) / (next.time - frame.time) as f64)
+                    .clamp(0.0, 1.0) as f32;
+                (
+                    frame.x + (next.x - frame.x) * t,
+                    frame.y + (next.y - frame.y) * t,
+                )
+            }
+            _ => (frame.x, frame.y),
+        };
+
+        resampled.push(FlatFrame {
+            time: tick_time.round() as i64,
+            x,
+            y,
+            keys: frame.keys,
+        });
+    }
+
+    resampled
+}
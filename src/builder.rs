@@ -0,0 +1,209 @@
+//! A builder for creating replays field by field.
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    replay::Replay,
+    types::{GameMode, LifeBarState, ManiaSettings, Mod, ReplayEvent},
+};
+
+/// Builds a [`Replay`] from scratch, or from an existing replay with [`Replay::to_builder`].
+///
+/// Fields that aren't set keep their defaults: empty strings, zero counts, no
+/// mods, no life bar, no frames, the current time as the timestamp and the
+/// current date as the game version.
+///
+/// # Example
+///
+/// ```rust
+/// use rosu_replay::{GameMode, Mod, ReplayBuilder};
+///
+/// let replay = ReplayBuilder::new(GameMode::Std)
+///     .with_username("TestPlayer")
+///     .with_score(1_000_000)
+///     .with_mods(Mod::HIDDEN)
+///     .build();
+/// assert_eq!(replay.username, "TestPlayer");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplayBuilder {
+    replay: Replay,
+}
+
+impl Default for ReplayBuilder {
+    fn default() -> Self {
+        Self::new(GameMode::Std)
+    }
+}
+
+impl From<Replay> for ReplayBuilder {
+    fn from(replay: Replay) -> Self {
+        Self { replay }
+    }
+}
+
+impl ReplayBuilder {
+    pub fn new(mode: GameMode) -> Self {
+        let timestamp = Utc::now();
+        let mut replay = Replay {
+            mode,
+            game_version: 0,
+            beatmap_hash: String::new(),
+            username: String::new(),
+            replay_hash: String::new(),
+            count_300: 0,
+            count_100: 0,
+            count_50: 0,
+            count_geki: 0,
+            count_katu: 0,
+            count_miss: 0,
+            score: 0,
+            max_combo: 0,
+            perfect: false,
+            mods: Mod::NO_MOD,
+            life_bar_graph: None,
+            timestamp,
+            replay_data: Vec::new(),
+            replay_id: 0,
+            rng_seed: None,
+            target_practice: None,
+            mania_settings: None,
+            lazer_data: None,
+            mode_byte: None,
+        };
+        replay.set_game_version_from_date(timestamp.date_naive());
+
+        Self { replay }
+    }
+
+    pub fn with_mode(mut self, mode: GameMode) -> Self {
+        self.replay.mode = mode;
+        self
+    }
+
+    pub fn with_game_version(mut self, game_version: u32) -> Self {
+        self.replay.game_version = game_version;
+        self
+    }
+
+    pub fn with_beatmap_hash(mut self, beatmap_hash: impl Into<String>) -> Self {
+        self.replay.beatmap_hash = beatmap_hash.into();
+        self
+    }
+
+    pub fn with_username(mut self, username: impl Into<String>) -> Self {
+        self.replay.username = username.into();
+        self
+    }
+
+    pub fn with_replay_hash(mut self, replay_hash: impl Into<String>) -> Self {
+        self.replay.replay_hash = replay_hash.into();
+        self
+    }
+
+    pub fn with_count_300(mut self, count: u16) -> Self {
+        self.replay.count_300 = count;
+        self
+    }
+
+    pub fn with_count_100(mut self, count: u16) -> Self {
+        self.replay.count_100 = count;
+        self
+    }
+
+    pub fn with_count_50(mut self, count: u16) -> Self {
+        self.replay.count_50 = count;
+        self
+    }
+
+    pub fn with_count_geki(mut self, count: u16) -> Self {
+        self.replay.count_geki = count;
+        self
+    }
+
+    pub fn with_count_katu(mut self, count: u16) -> Self {
+        self.replay.count_katu = count;
+        self
+    }
+
+    pub fn with_count_miss(mut self, count: u16) -> Self {
+        self.replay.count_miss = count;
+        self
+    }
+
+    pub fn with_score(mut self, score: u32) -> Self {
+        self.replay.score = score;
+        self
+    }
+
+    pub fn with_max_combo(mut self, max_combo: u16) -> Self {
+        self.replay.max_combo = max_combo;
+        self
+    }
+
+    pub fn with_perfect(mut self, perfect: bool) -> Self {
+        self.replay.perfect = perfect;
+        self
+    }
+
+    pub fn with_mods(mut self, mods: Mod) -> Self {
+        self.replay.mods = mods;
+        self
+    }
+
+    pub fn with_life_bar_graph(mut self, life_bar_graph: Option<Vec<LifeBarState>>) -> Self {
+        self.replay.life_bar_graph = life_bar_graph;
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.replay.timestamp = timestamp;
+        self
+    }
+
+    pub fn with_replay_data(mut self, replay_data: Vec<ReplayEvent>) -> Self {
+        self.replay.replay_data = replay_data;
+        self
+    }
+
+    pub fn with_replay_id(mut self, replay_id: i64) -> Self {
+        self.replay.replay_id = replay_id;
+        self
+    }
+
+    pub fn with_rng_seed(mut self, rng_seed: Option<i32>) -> Self {
+        self.replay.rng_seed = rng_seed;
+        self
+    }
+
+    pub fn with_target_practice(mut self, target_practice: Option<f64>) -> Self {
+        self.replay.target_practice = target_practice;
+        self
+    }
+
+    pub fn with_mania_settings(mut self, mania_settings: Option<ManiaSettings>) -> Self {
+        self.replay.mania_settings = mania_settings;
+        self
+    }
+
+    pub fn with_lazer_data(mut self, lazer_data: Option<serde_json::Value>) -> Self {
+        self.replay.lazer_data = lazer_data;
+        self
+    }
+
+    /// Returns the built replay.
+    pub fn build(self) -> Replay {
+        self.replay
+    }
+}
+
+impl Replay {
+    /// Turns this replay into a builder, to change a few fields and rebuild it.
+    ///
+    /// # Returns
+    ///
+    /// A builder holding every field of this replay
+    pub fn to_builder(self) -> ReplayBuilder {
+        ReplayBuilder::from(self)
+    }
+}
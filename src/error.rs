@@ -3,7 +3,12 @@
 use thiserror::Error;
 
 /// Errors that can occur when parsing or writing replay files.
+///
+/// New variants may be added in minor releases, so matches on this type need
+/// a wildcard arm. Use the constructors such as [`ReplayError::parse`] to build
+/// errors without depending on the inner representation.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum ReplayError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -28,4 +33,24 @@ pub enum ReplayError {
 
     #[error("LZMA decompression error: {0}")]
     Lzma(#[from] liblzma::stream::Error),
+
+    #[error("Unsupported game version: {0}")]
+    UnsupportedVersion(u32),
+}
+
+impl ReplayError {
+    /// Creates a [`ReplayError::Parse`] error with the given message.
+    pub fn parse(message: impl std::fmt::Display) -> Self {
+        Self::Parse(message.to_string())
+    }
+
+    /// Creates a [`ReplayError::InvalidFormat`] error with the given message.
+    pub fn invalid_format(message: impl std::fmt::Display) -> Self {
+        Self::InvalidFormat(message.to_string())
+    }
+
+    /// Creates a [`ReplayError::LzmaCustom`] error with the given message.
+    pub fn lzma(message: impl std::fmt::Display) -> Self {
+        Self::LzmaCustom(message.to_string())
+    }
 }
@@ -77,8 +77,12 @@
 //!
 //! See the `examples/` directory for more comprehensive usage examples.
 
+pub mod analysis;
+pub mod builder;
 pub mod error;
 pub mod packer;
+#[cfg(feature = "prost")]
+pub mod proto;
 pub mod replay;
 pub mod types;
 pub mod unpacker;
@@ -86,10 +90,15 @@ pub mod unpacker;
 #[cfg(feature = "wasm")]
 pub mod wasm;
 
+pub use analysis::{DeltaStats, FlatFrame, ManiaLaneEvent};
+pub use builder::ReplayBuilder;
 pub use error::ReplayError;
-pub use packer::Packer;
-pub use replay::Replay;
+pub use packer::{LzmaFormat, Packer, PackerOptions};
+#[cfg(feature = "prost")]
+pub use proto::{FrameProto, FramesProto};
+pub use replay::{FramesGuard, Replay};
 pub use types::*;
+pub use unpacker::{ParseReport, ParseStage, ParserConfig};
 
 /// Parse replay data from a string (for API usage)
 pub fn parse_replay_data(
@@ -100,3 +109,13 @@ pub fn parse_replay_data(
 ) -> Result<Vec<ReplayEvent>, ReplayError> {
     replay::parse_replay_data(data_string, decoded, decompressed, mode)
 }
+
+/// Parse replay data from a string (for API usage), rejecting malformed frames
+pub fn parse_replay_data_strict(
+    data_string: &[u8],
+    decoded: bool,
+    decompressed: bool,
+    mode: GameMode,
+) -> Result<Vec<ReplayEvent>, ReplayError> {
+    replay::parse_replay_data_strict(data_string, decoded, decompressed, mode)
+}
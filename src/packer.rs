@@ -1,22 +1,67 @@
 use crate::{error::ReplayError, replay::Replay, types::*};
 use byteorder::{LittleEndian, WriteBytesExt};
-use liblzma::{stream::LzmaOptions, write::XzEncoder};
+use liblzma::{
+    stream::{Check, LzmaOptions, Stream},
+    write::XzEncoder,
+};
 use std::io::Write;
 
-/// Helper struct for packing data into .osr format
-pub struct Packer {
-    preset: u32,
+/// The container format of LZMA-compressed data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LzmaFormat {
+    /// The headerless `.lzma` ("LZMA alone") format written by osu!
+    #[default]
+    Alone,
+    /// The `.xz` container format
+    Xz,
 }
 
-impl Default for Packer {
+/// Options controlling how a replay is packed into .osr format.
+///
+/// # Example
+///
+/// ```rust
+/// use rosu_replay::PackerOptions;
+///
+/// let options = PackerOptions::new()
+///     .with_preset(9)
+///     .with_life_bar(false)
+///     .with_coordinate_precision(Some(2));
+/// assert_eq!(options.preset, 9);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackerOptions {
+    /// The LZMA compression preset (0-9)
+    pub preset: u32,
+    /// Whether the replay data is LZMA compressed
+    pub compress: bool,
+    /// Whether the life bar is written (an absent life bar is written as null)
+    pub include_life_bar: bool,
+    /// Whether the rng seed frame is written
+    pub include_rng_seed: bool,
+    /// Whether a `-12345|0|0|0` placeholder seed frame is written for replays without a seed
+    pub always_emit_seed_frame: bool,
+    /// The number of decimal places written for coordinates, or `None` for the shortest exact form
+    pub coordinate_precision: Option<usize>,
+    /// The LZMA format the replay data is compressed with
+    pub lzma_format: LzmaFormat,
+}
+
+impl Default for PackerOptions {
     fn default() -> Self {
         Self {
             preset: 6, // Default compression level
+            compress: true,
+            include_life_bar: true,
+            include_rng_seed: true,
+            always_emit_seed_frame: false,
+            coordinate_precision: None,
+            lzma_format: LzmaFormat::Alone,
         }
     }
 }
 
-impl Packer {
+impl PackerOptions {
     pub fn new() -> Self {
         Self::default()
     }
@@ -26,6 +71,68 @@ impl Packer {
         self
     }
 
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    pub fn with_life_bar(mut self, include_life_bar: bool) -> Self {
+        self.include_life_bar = include_life_bar;
+        self
+    }
+
+    pub fn with_rng_seed(mut self, include_rng_seed: bool) -> Self {
+        self.include_rng_seed = include_rng_seed;
+        self
+    }
+
+    pub fn with_always_emit_seed_frame(mut self, always_emit: bool) -> Self {
+        self.always_emit_seed_frame = always_emit;
+        self
+    }
+
+    pub fn with_coordinate_precision(mut self, precision: Option<usize>) -> Self {
+        self.coordinate_precision = precision;
+        self
+    }
+
+    pub fn with_lzma_format(mut self, lzma_format: LzmaFormat) -> Self {
+        self.lzma_format = lzma_format;
+        self
+    }
+}
+
+/// Helper struct for packing data into .osr format
+#[derive(Default)]
+pub struct Packer {
+    options: PackerOptions,
+}
+
+impl From<PackerOptions> for Packer {
+    fn from(options: PackerOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Packer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(options: PackerOptions) -> Self {
+        Self::from(options)
+    }
+
+    pub fn with_preset(mut self, preset: u32) -> Self {
+        self.options.preset = preset;
+        self
+    }
+
+    /// Returns the options this packer was configured with.
+    pub fn options(&self) -> &PackerOptions {
+        &self.options
+    }
+
     fn pack_byte(&self, writer: &mut impl Write, data: u8) -> Result<(), ReplayError> {
         writer.write_u8(data)?;
         Ok(())
@@ -104,7 +211,10 @@ impl Packer {
         writer: &mut impl Write,
         life_bar_graph: &Option<Vec<LifeBarState>>,
     ) -> Result<(), ReplayError> {
-        match life_bar_graph {
+        match life_bar_graph
+            .as_ref()
+            .filter(|_| self.options.include_life_bar)
+        {
             None => {
                 self.pack_string(writer, None)?;
             }
@@ -118,129 +228,188 @@ impl Packer {
                     };
                     data.push_str(&format!("{}|{},", state.time, life));
                 }
-                self.pack_string(writer, Some(&data))?;
+
+                if data.is_empty() {
+                    // `pack_string` writes empty strings as null, but osu!
+                    // tells an empty life bar apart from a missing one
+                    self.pack_byte(writer, 0x0b)?;
+                    self.pack_uleb128(writer, 0)?;
+                } else {
+                    self.pack_string(writer, Some(&data))?;
+                }
             }
         }
         Ok(())
     }
 
-    fn pack_replay_data(
+    fn format_coordinate(&self, value: f32) -> String {
+        match self.options.coordinate_precision {
+            Some(precision) => format!("{:.*}", precision, value),
+            None => value.to_string(),
+        }
+    }
+
+    /// Checks that a formatted frame can be read back by the unpacker.
+    ///
+    /// A frame must be exactly four `|`-separated fields without any `,`, which
+    /// separates frames. This catches formatting bugs (such as a locale-aware
+    /// float using `,` as its decimal separator) at write time rather than
+    /// producing an unparseable replay.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - A single formatted frame, without its trailing comma
+    pub fn check_frame(frame: &str) -> Result<(), ReplayError> {
+        if frame.contains(',') || frame.matches('|').count() != 3 {
+            return Err(ReplayError::InvalidFormat(format!(
+                "Formatted frame contains stray separators: {:?}",
+                frame
+            )));
+        }
+        Ok(())
+    }
+
+    /// Formats a single frame as `time_delta|x|y|keys`, without its trailing comma.
+    pub(crate) fn format_frame(&self, event: &ReplayEvent) -> String {
+        match event {
+            ReplayEvent::Osu(event) => format!(
+                "{}|{}|{}|{}",
+                event.time_delta,
+                self.format_coordinate(event.x),
+                self.format_coordinate(event.y),
+                event.keys.value()
+            ),
+            ReplayEvent::Taiko(event) => {
+                format!("{}|{}|0|{}", event.time_delta, event.x, event.keys.value())
+            }
+            ReplayEvent::Catch(event) => format!(
+                "{}|{}|0|{}",
+                event.time_delta,
+                self.format_coordinate(event.x),
+                if event.dashing { 1 } else { 0 }
+            ),
+            ReplayEvent::Mania(event) => {
+                format!("{}|{}|0|0", event.time_delta, event.keys.value())
+            }
+        }
+    }
+
+    /// Formats replay frames as the uncompressed frame block written to `.osr` files.
+    ///
+    /// Like osu!, every frame is followed by a comma, including the last one,
+    /// so the block ends with `,` and has no other terminator. When the seed is
+    /// included, the last frame is the `-12345|0|0|<seed>` seed frame; with
+    /// [`PackerOptions::always_emit_seed_frame`] a missing seed is written as 0.
+    pub(crate) fn format_replay_data(
         &self,
-        writer: &mut impl Write,
         replay_data: &[ReplayEvent],
         rng_seed: Option<i32>,
-    ) -> Result<(), ReplayError> {
+    ) -> Result<String, ReplayError> {
         let mut data = String::new();
 
         for event in replay_data {
-            match event {
-                ReplayEvent::Osu(event) => {
-                    data.push_str(&format!(
-                        "{}|{}|{}|{},",
-                        event.time_delta,
-                        event.x,
-                        event.y,
-                        event.keys.value()
-                    ));
-                }
-                ReplayEvent::Taiko(event) => {
-                    data.push_str(&format!(
-                        "{}|{}|0|{},",
-                        event.time_delta,
-                        event.x,
-                        event.keys.value()
-                    ));
-                }
-                ReplayEvent::Catch(event) => {
-                    data.push_str(&format!(
-                        "{}|{}|0|{},",
-                        event.time_delta,
-                        event.x,
-                        if event.dashing { 1 } else { 0 }
-                    ));
-                }
-                ReplayEvent::Mania(event) => {
-                    data.push_str(&format!("{}|{}|0|0,", event.time_delta, event.keys.value()));
-                }
+            let finite = match event {
+                ReplayEvent::Osu(event) => event.x.is_finite() && event.y.is_finite(),
+                ReplayEvent::Catch(event) => event.x.is_finite(),
+                ReplayEvent::Taiko(_) | ReplayEvent::Mania(_) => true,
+            };
+            if !finite {
+                return Err(ReplayError::InvalidFormat(format!(
+                    "Frame has a non-finite coordinate: {:?}",
+                    event
+                )));
             }
-        }
 
-        if let Some(seed) = rng_seed {
-            data.push_str(&format!("-12345|0|0|{},", seed));
+            let frame = self.format_frame(event);
+            Self::check_frame(&frame)?;
+            data.push_str(&frame);
+            data.push(',');
         }
 
-        // Compress the data
-        let data_bytes = data.as_bytes();
-        let mut compressed = Vec::with_capacity(data_bytes.len());
+        // Some tools expect the seed frame even when there is no seed, which
+        // reads back as a seed of 0
+        let seed = rng_seed.or(self.options.always_emit_seed_frame.then_some(0));
+        if let Some(seed) = seed.filter(|_| self.options.include_rng_seed) {
+            // The seed is read back from the unsigned keys field
+            data.push_str(&format!("-12345|0|0|{},", seed as u32));
+        }
 
-        let lzma_stream = liblzma::stream::Stream::new_lzma_encoder(&LzmaOptions::new_preset(6)?)?;
+        Ok(data)
+    }
 
-        let mut encoder = XzEncoder::new_stream(&mut compressed, lzma_stream);
+    fn pack_replay_data(
+        &self,
+        writer: &mut impl Write,
+        replay_data: &[ReplayEvent],
+        rng_seed: Option<i32>,
+        compress: bool,
+    ) -> Result<(), ReplayError> {
+        let data = self.format_replay_data(replay_data, rng_seed)?;
+        let data_bytes = data.as_bytes();
 
-        encoder.write_all(data_bytes)?;
-        encoder.finish()?;
+        if !compress {
+            // Write length and uncompressed data
+            self.pack_int(writer, data_bytes.len() as u32)?;
+            writer.write_all(data_bytes)?;
+            return Ok(());
+        }
 
         // Write length and compressed data
+        let compressed = self.compress(data_bytes)?;
         self.pack_int(writer, compressed.len() as u32)?;
         writer.write_all(&compressed)?;
 
         Ok(())
     }
 
-    fn pack_replay_data_uncompressed(
-        &self,
-        writer: &mut impl Write,
-        replay_data: &[ReplayEvent],
-        rng_seed: Option<i32>,
-    ) -> Result<(), ReplayError> {
-        let mut data = String::new();
+    /// Compresses data the way this packer compresses the frame block.
+    ///
+    /// This uses the configured compression preset and LZMA format.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to compress
+    ///
+    /// # Returns
+    ///
+    /// The compressed bytes
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, ReplayError> {
+        self.compress_bytes(data, self.options.lzma_format)
+    }
 
-        for event in replay_data {
-            match event {
-                ReplayEvent::Osu(event) => {
-                    data.push_str(&format!(
-                        "{}|{}|{}|{},",
-                        event.time_delta,
-                        event.x,
-                        event.y,
-                        event.keys.value()
-                    ));
-                }
-                ReplayEvent::Taiko(event) => {
-                    data.push_str(&format!(
-                        "{}|{}|0|{},",
-                        event.time_delta,
-                        event.x,
-                        event.keys.value()
-                    ));
-                }
-                ReplayEvent::Catch(event) => {
-                    data.push_str(&format!(
-                        "{}|{}|0|{},",
-                        event.time_delta,
-                        event.x,
-                        if event.dashing { 1 } else { 0 }
-                    ));
-                }
-                ReplayEvent::Mania(event) => {
-                    data.push_str(&format!("{}|{}|0|0,", event.time_delta, event.keys.value()));
-                }
+    fn compress_bytes(&self, data: &[u8], format: LzmaFormat) -> Result<Vec<u8>, ReplayError> {
+        let mut compressed = Vec::with_capacity(data.len());
+
+        let lzma_stream = match format {
+            LzmaFormat::Alone => {
+                Stream::new_lzma_encoder(&LzmaOptions::new_preset(self.options.preset)?)?
             }
-        }
+            LzmaFormat::Xz => Stream::new_easy_encoder(self.options.preset, Check::Crc64)?,
+        };
 
-        if let Some(seed) = rng_seed {
-            data.push_str(&format!("-12345|0|0|{},", seed));
-        }
+        let mut encoder = XzEncoder::new_stream(&mut compressed, lzma_stream);
 
-        // Write length and uncompressed data
-        let data_bytes = data.as_bytes();
-        self.pack_int(writer, data_bytes.len() as u32)?;
-        writer.write_all(data_bytes)?;
+        encoder.write_all(data)?;
+        encoder.finish()?;
+
+        Ok(compressed)
+    }
+
+    fn pack_lazer_data(&self, writer: &mut impl Write, replay: &Replay) -> Result<(), ReplayError> {
+        // Stable replays end after the replay id
+        let Some(data) = replay.written_lazer_data() else {
+            return Ok(());
+        };
+
+        // Lazer always reads this block as LZMA alone
+        let compressed = self.compress_bytes(data.to_string().as_bytes(), LzmaFormat::Alone)?;
+        self.pack_int(writer, compressed.len() as u32)?;
+        writer.write_all(&compressed)?;
 
         Ok(())
     }
 
-    pub fn pack(&self, replay: &Replay) -> Result<Vec<u8>, ReplayError> {
+    fn pack_replay(&self, replay: &Replay, compress: bool) -> Result<Vec<u8>, ReplayError> {
         let mut buffer = Vec::new();
 
         self.pack_byte(&mut buffer, replay.mode as u8)?;
@@ -260,12 +429,29 @@ impl Packer {
         self.pack_int(&mut buffer, replay.mods.value())?;
         self.pack_life_bar(&mut buffer, &replay.life_bar_graph)?;
         self.pack_timestamp(&mut buffer, &replay.timestamp)?;
-        self.pack_replay_data(&mut buffer, &replay.replay_data, replay.rng_seed)?;
+        self.pack_replay_data(&mut buffer, &replay.replay_data, replay.rng_seed, compress)?;
         self.pack_long(&mut buffer, replay.replay_id)?;
+        if let Some(accuracy) = replay.written_target_practice() {
+            buffer.write_f64::<LittleEndian>(accuracy)?;
+        }
+        self.pack_lazer_data(&mut buffer, replay)?;
 
         Ok(buffer)
     }
 
+    /// Packs a replay into `.osr` format using this packer's options.
+    ///
+    /// # Arguments
+    ///
+    /// * `replay` - The replay to pack
+    ///
+    /// # Returns
+    ///
+    /// The bytes representing this `Replay` in `.osr` format
+    pub fn pack(&self, replay: &Replay) -> Result<Vec<u8>, ReplayError> {
+        self.pack_replay(replay, self.options.compress)
+    }
+
     /// Packs a replay without LZMA compression on the replay data.
     ///
     /// This method is similar to `pack` but saves the replay data in uncompressed format,
@@ -280,28 +466,6 @@ impl Packer {
     ///
     /// The bytes representing this `Replay` in `.osr` format without LZMA compression
     pub fn pack_uncompressed(&self, replay: &Replay) -> Result<Vec<u8>, ReplayError> {
-        let mut buffer = Vec::new();
-
-        self.pack_byte(&mut buffer, replay.mode as u8)?;
-        self.pack_int(&mut buffer, replay.game_version)?;
-        self.pack_string(&mut buffer, Some(&replay.beatmap_hash))?;
-        self.pack_string(&mut buffer, Some(&replay.username))?;
-        self.pack_string(&mut buffer, Some(&replay.replay_hash))?;
-        self.pack_short(&mut buffer, replay.count_300)?;
-        self.pack_short(&mut buffer, replay.count_100)?;
-        self.pack_short(&mut buffer, replay.count_50)?;
-        self.pack_short(&mut buffer, replay.count_geki)?;
-        self.pack_short(&mut buffer, replay.count_katu)?;
-        self.pack_short(&mut buffer, replay.count_miss)?;
-        self.pack_int(&mut buffer, replay.score)?;
-        self.pack_short(&mut buffer, replay.max_combo)?;
-        self.pack_byte(&mut buffer, if replay.perfect { 1 } else { 0 })?;
-        self.pack_int(&mut buffer, replay.mods.value())?;
-        self.pack_life_bar(&mut buffer, &replay.life_bar_graph)?;
-        self.pack_timestamp(&mut buffer, &replay.timestamp)?;
-        self.pack_replay_data_uncompressed(&mut buffer, &replay.replay_data, replay.rng_seed)?;
-        self.pack_long(&mut buffer, replay.replay_id)?;
-
-        Ok(buffer)
+        self.pack_replay(replay, false)
     }
 }
@@ -0,0 +1,124 @@
+//! Protobuf messages for transferring replay frames between services.
+//!
+//! The messages are declared with `prost` derives, matching this schema:
+//!
+//! ```proto
+//! message FrameProto {
+//!   sint32 time_delta = 1;
+//!   float x = 2;
+//!   float y = 3;
+//!   uint32 keys = 4;
+//! }
+//!
+//! message FramesProto {
+//!   uint32 mode = 1;
+//!   repeated FrameProto frames = 2;
+//! }
+//! ```
+
+use crate::{replay::Replay, types::*};
+
+/// A single replay frame.
+///
+/// Fields a mode doesn't use are 0: `y` outside of osu!standard, `x` in
+/// osu!mania. `keys` holds the raw key bits, with bit 0 as the dash state in
+/// osu!catch.
+#[derive(Clone, Copy, PartialEq, prost::Message)]
+pub struct FrameProto {
+    #[prost(sint32, tag = "1")]
+    pub time_delta: i32,
+    #[prost(float, tag = "2")]
+    pub x: f32,
+    #[prost(float, tag = "3")]
+    pub y: f32,
+    #[prost(uint32, tag = "4")]
+    pub keys: u32,
+}
+
+/// The frames of a replay along with the game mode needed to decode them.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FramesProto {
+    #[prost(uint32, tag = "1")]
+    pub mode: u32,
+    #[prost(message, repeated, tag = "2")]
+    pub frames: Vec<FrameProto>,
+}
+
+impl From<&ReplayEvent> for FrameProto {
+    fn from(event: &ReplayEvent) -> Self {
+        let (x, y) = match event {
+            ReplayEvent::Osu(event) => (event.x, event.y),
+            ReplayEvent::Taiko(event) => (event.x as f32, 0.0),
+            ReplayEvent::Catch(event) => (event.x, 0.0),
+            ReplayEvent::Mania(_) => (0.0, 0.0),
+        };
+
+        Self {
+            time_delta: event.time_delta(),
+            x,
+            y,
+            keys: event.key_bits(),
+        }
+    }
+}
+
+impl FrameProto {
+    fn into_event(self, mode: GameMode) -> ReplayEvent {
+        match mode {
+            GameMode::Std => ReplayEvent::Osu(ReplayEventOsu {
+                time_delta: self.time_delta,
+                x: self.x,
+                y: self.y,
+                keys: Key(self.keys),
+            }),
+            GameMode::Taiko => ReplayEvent::Taiko(ReplayEventTaiko {
+                time_delta: self.time_delta,
+                x: self.x as i32,
+                keys: KeyTaiko(self.keys),
+            }),
+            GameMode::Catch => ReplayEvent::Catch(ReplayEventCatch {
+                time_delta: self.time_delta,
+                x: self.x,
+                dashing: self.keys & 1 != 0,
+            }),
+            GameMode::Mania => ReplayEvent::Mania(ReplayEventMania {
+                time_delta: self.time_delta,
+                keys: KeyMania(self.keys),
+            }),
+        }
+    }
+}
+
+impl Replay {
+    /// Converts the frames of this replay into a protobuf message.
+    ///
+    /// Encode the result with [`prost::Message::encode_to_vec`].
+    ///
+    /// # Returns
+    ///
+    /// The game mode and frames of this replay
+    pub fn to_proto(&self) -> FramesProto {
+        FramesProto {
+            mode: self.mode as u32,
+            frames: self.replay_data.iter().map(FrameProto::from).collect(),
+        }
+    }
+
+    /// Converts a protobuf message back into replay frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `proto` - The message, as produced by [`Replay::to_proto`]
+    ///
+    /// # Returns
+    ///
+    /// The frames of the message, as events of its game mode
+    pub fn from_proto(proto: FramesProto) -> Vec<ReplayEvent> {
+        let mode = GameMode::from(proto.mode as u8);
+        proto
+            .frames
+            .into_iter()
+            .map(|frame| frame.into_event(mode))
+            .collect()
+    }
+}
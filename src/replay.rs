@@ -1,12 +1,18 @@
 use base64::{engine::general_purpose, Engine as _};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, SubsecRound, Utc};
 use liblzma::decode_all;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Cursor};
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
 
-use crate::{error::ReplayError, packer::Packer, types::*, unpacker::Unpacker};
+use crate::{
+    error::ReplayError,
+    packer::{Packer, PackerOptions},
+    types::*,
+    unpacker::{ParseReport, ParseStage, ParserConfig, Unpacker, LAZER_GAME_VERSION},
+};
 
 /// A replay found in a `.osr` file, or following the osr format.
 ///
@@ -36,6 +42,11 @@ pub struct Replay {
     /// The number of misses in this replay
     pub count_miss: u16,
     /// The score of this replay
+    ///
+    /// The `.osr` format stores the score as an unsigned 32-bit integer, so
+    /// every value up to `u32::MAX` is read and written unchanged. Lazer keeps
+    /// 64-bit totals internally but still writes this 32-bit field when
+    /// exporting; see [`Replay::score_i64`] for arithmetic without overflow.
     pub score: u32,
     /// The maximum combo attained in this replay
     pub max_combo: u16,
@@ -44,15 +55,58 @@ pub struct Replay {
     /// The mods this replay was played with
     pub mods: Mod,
     /// The life bar of this replay over time
+    ///
+    /// `None` stands for a null life bar string and an empty list for an empty
+    /// but present one; `.osr` files tell the two apart.
     pub life_bar_graph: Option<Vec<LifeBarState>>,
     /// The timestamp when this replay was played
     pub timestamp: DateTime<Utc>,
     /// The replay data of the replay, including cursor position and keys pressed
+    ///
+    /// Each frame's `time_delta` is relative to the previous frame. Apart from
+    /// the setup frames osu! writes before gameplay (the `0` and `-1` deltas
+    /// before the first positive delta), deltas are expected to be
+    /// non-negative. Prefer [`Replay::frames`] for reading and
+    /// [`Replay::frames_mut_checked`] for editing, which keeps this invariant.
     pub replay_data: Vec<ReplayEvent>,
     /// The replay id of this replay, or 0 if not submitted
     pub replay_id: i64,
     /// The rng seed of this replay, or None if not present
+    ///
+    /// osu! stores the seed in the unsigned keys field of the seed frame, so
+    /// seeds at or above 2^31 appear negative here. Use [`Replay::rng_seed_u32`]
+    /// to read the seed as osu! wrote it.
     pub rng_seed: Option<i32>,
+    /// The target practice accuracy written after the replay id, or None if not present
+    ///
+    /// osu! only writes this value for replays played with [`Mod::TARGET`].
+    #[serde(default)]
+    pub target_practice: Option<f64>,
+    /// The osu!mania settings stored in the lazer data block, or None if not present
+    ///
+    /// osu!lazer itself doesn't write these settings: they are read from and
+    /// written to a `mania_settings` key of [`Replay::lazer_data`], a
+    /// convention of this crate. When packing, this field replaces that key.
+    /// Stable replays end after the replay id (or the target practice
+    /// accuracy) and always leave this `None`.
+    #[serde(default)]
+    pub mania_settings: Option<ManiaSettings>,
+    /// The JSON document of the lazer data block, or None if not present
+    ///
+    /// The block is only read and written for lazer replays, whose
+    /// `game_version` is at least
+    /// [`LAZER_GAME_VERSION`](crate::unpacker::LAZER_GAME_VERSION). It is kept
+    /// as read so that packing a lazer replay doesn't drop its score
+    /// information.
+    #[serde(default)]
+    pub lazer_data: Option<serde_json::Value>,
+    /// The mode byte as read from the file, or None if this replay wasn't parsed
+    ///
+    /// With [`ParserConfig::lenient_mode`], unknown mode bytes are read as
+    /// [`GameMode::Std`], so this keeps the original value. See
+    /// [`Replay::raw_mode_byte`].
+    #[serde(default)]
+    pub mode_byte: Option<u8>,
 }
 
 impl Replay {
@@ -85,6 +139,83 @@ impl Replay {
         unpacker.unpack()
     }
 
+    /// Creates a new `Replay` object from a seekable reader.
+    ///
+    /// Unlike [`Replay::from_reader`], this can tell a 4-byte replay id at the
+    /// end of very old replays from the usual 8-byte one, by checking how many
+    /// bytes are left in the stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read from
+    ///
+    /// # Returns
+    ///
+    /// The parsed replay object
+    pub fn from_reader_seek<R: std::io::Read + std::io::Seek>(
+        reader: R,
+    ) -> Result<Self, ReplayError> {
+        Unpacker::new(reader).unpack_seekable()
+    }
+
+    /// Creates a new `Replay` object from a reader with custom parser options.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read from
+    /// * `config` - The options controlling how strictly the replay is parsed
+    ///
+    /// # Returns
+    ///
+    /// The parsed replay object
+    pub fn from_reader_with_config<R: std::io::Read>(
+        reader: R,
+        config: &ParserConfig,
+    ) -> Result<Self, ReplayError> {
+        Unpacker::with_config(reader, config.clone()).unpack()
+    }
+
+    /// Creates a new `Replay` object from a reader with custom parser options,
+    /// along with the recoverable problems found while parsing it.
+    ///
+    /// Use this with lenient options to learn how much was skipped, such as
+    /// the malformed life bar states dropped by
+    /// [`ParserConfig::lenient_life_bar`].
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read from
+    /// * `config` - The options controlling how strictly the replay is parsed
+    ///
+    /// # Returns
+    ///
+    /// The parsed replay object and the [`ParseReport`] of its parse
+    pub fn from_reader_with_report<R: std::io::Read>(
+        reader: R,
+        config: &ParserConfig,
+    ) -> Result<(Self, ParseReport), ReplayError> {
+        Unpacker::with_config(reader, config.clone()).unpack_with_report()
+    }
+
+    /// Creates a new `Replay` object from a reader, reporting parsing progress.
+    ///
+    /// This is useful for showing a progress bar while parsing large replays.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader to read from
+    /// * `progress` - The callback invoked at each [`ParseStage`]
+    ///
+    /// # Returns
+    ///
+    /// The parsed replay object
+    pub fn from_reader_with_progress<R: std::io::Read, F: FnMut(ParseStage)>(
+        reader: R,
+        progress: F,
+    ) -> Result<Self, ReplayError> {
+        Unpacker::new(reader).unpack_with_progress(progress)
+    }
+
     /// Creates a new `Replay` object from a byte slice containing `.osr` data.
     ///
     /// # Arguments
@@ -99,6 +230,23 @@ impl Replay {
         Self::from_reader(cursor)
     }
 
+    /// Reads only the metadata a listing needs from `.osr` data.
+    ///
+    /// Parsing stops right after the mods, so the life bar and the compressed
+    /// frames are never read or decompressed. This is much faster than
+    /// [`Replay::from_bytes`] when only a few header fields are needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The data to read from
+    ///
+    /// # Returns
+    ///
+    /// The mode, username, beatmap hash, score, max combo and mods of the replay
+    pub fn quick_info(data: &[u8]) -> Result<QuickInfo, ReplayError> {
+        Unpacker::new(Cursor::new(data)).unpack_quick_info()
+    }
+
     /// Writes the replay to the given path.
     ///
     /// # Arguments
@@ -111,22 +259,95 @@ impl Replay {
     /// create an edited version of a replay, by first reading a replay, editing
     /// an attribute, then writing the replay back to its file.
     pub fn write_path<P: AsRef<Path>>(&self, path: P) -> Result<(), ReplayError> {
+        self.write_path_counted(path).map(|_| ())
+    }
+
+    /// Writes the replay to the given path, reporting how many bytes were written.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to where to write the replay
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written
+    pub fn write_path_counted<P: AsRef<Path>>(&self, path: P) -> Result<usize, ReplayError> {
         let file = File::create(path)?;
         let writer = BufWriter::new(file);
-        self.write_to(writer)
+        self.write_to_counted(writer)
     }
 
     /// Writes the replay to a writer.
     ///
+    /// The writer is flushed before returning, so write errors are reported
+    /// here rather than lost when a buffered writer is dropped.
+    ///
     /// # Arguments
     ///
     /// * `writer` - The writer to write to
-    pub fn write_to<W: std::io::Write>(&self, mut writer: W) -> Result<(), ReplayError> {
+    pub fn write_to<W: std::io::Write>(&self, writer: W) -> Result<(), ReplayError> {
+        self.write_to_counted(writer).map(|_| ())
+    }
+
+    /// Writes the replay to a writer, reporting how many bytes were written.
+    ///
+    /// The writer is flushed before returning, like [`Replay::write_to`].
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer to write to
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written
+    pub fn write_to_counted<W: std::io::Write>(&self, mut writer: W) -> Result<usize, ReplayError> {
         let packed = self.pack()?;
         writer.write_all(&packed)?;
+        writer.flush()?;
+        Ok(packed.len())
+    }
+
+    /// Writes the frames of this replay as newline-delimited JSON.
+    ///
+    /// Each line is one [`FlatFrame`](crate::FlatFrame) object holding the
+    /// absolute time, position and key bits of a frame, which suits log
+    /// pipelines and other stream processors.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The writer to write to
+    pub fn to_ndjson<W: std::io::Write>(&self, mut writer: W) -> Result<(), ReplayError> {
+        for frame in self.flat_frames() {
+            serde_json::to_writer(&mut writer, &frame).map_err(std::io::Error::from)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
         Ok(())
     }
 
+    /// Returns the replay-side inputs of a pp calculation as a JSON object.
+    ///
+    /// This crate can't compute pp, as that needs the beatmap. The object holds
+    /// only what a calculator such as `rosu-pp` takes from the score: `mode`
+    /// (the ruleset short name), `mods` (API v2 acronyms), the hit counts
+    /// `n300`, `n100`, `n50`, `n_geki`, `n_katu` and `misses`, `max_combo` and
+    /// `accuracy` (in percent). Supply the beatmap to the calculator separately.
+    pub fn pp_context_json(&self) -> String {
+        serde_json::json!({
+            "mode": self.mode.short_name(),
+            "mods": self.mods.to_api_v2(),
+            "n300": self.count_300,
+            "n100": self.count_100,
+            "n50": self.count_50,
+            "n_geki": self.count_geki,
+            "n_katu": self.count_katu,
+            "misses": self.count_miss,
+            "max_combo": self.max_combo,
+            "accuracy": self.accuracy(),
+        })
+        .to_string()
+    }
+
     /// Returns the bytes representing this `Replay`, in `.osr` format.
     ///
     /// The bytes returned by this method are suitable for writing to a file as a
@@ -140,6 +361,146 @@ impl Replay {
         packer.pack(self)
     }
 
+    /// Packs this replay, reads it back and checks that nothing was lost.
+    ///
+    /// All metadata must match exactly, except for the timestamp, which `.osr`
+    /// files store with a precision of 100ns. Frames must have the same time
+    /// deltas and keys, with positions within a small tolerance. Replays with
+    /// non-finite positions can't be packed, so they return an error.
+    ///
+    /// # Returns
+    ///
+    /// Whether the reparsed replay matches this one
+    pub fn verify_round_trip(&self) -> Result<bool, ReplayError> {
+        const POSITION_EPSILON: f32 = 1e-3;
+
+        fn approx_eq(a: f32, b: f32) -> bool {
+            (a - b).abs() <= POSITION_EPSILON
+        }
+
+        let other = Self::from_bytes(&self.pack()?)?;
+
+        let metadata_matches = self.mode == other.mode
+            && self.game_version == other.game_version
+            && self.beatmap_hash == other.beatmap_hash
+            && self.username == other.username
+            && self.replay_hash == other.replay_hash
+            && self.count_300 == other.count_300
+            && self.count_100 == other.count_100
+            && self.count_50 == other.count_50
+            && self.count_geki == other.count_geki
+            && self.count_katu == other.count_katu
+            && self.count_miss == other.count_miss
+            && self.score == other.score
+            && self.max_combo == other.max_combo
+            && self.perfect == other.perfect
+            && self.mods == other.mods
+            && (self.timestamp - other.timestamp).abs() < chrono::Duration::nanoseconds(100)
+            && self.replay_id == other.replay_id
+            && self.rng_seed == other.rng_seed
+            && self.written_target_practice() == other.written_target_practice()
+            && self.mania_settings == other.mania_settings
+            && self.written_lazer_data() == other.written_lazer_data();
+
+        let life_bar_matches = match (&self.life_bar_graph, &other.life_bar_graph) {
+            (Some(life_bar), Some(other_life_bar)) => {
+                life_bar.len() == other_life_bar.len()
+                    && life_bar
+                        .iter()
+                        .zip(other_life_bar)
+                        .all(|(a, b)| a.time == b.time && approx_eq(a.life, b.life))
+            }
+            (life_bar, other_life_bar) => life_bar.is_none() && other_life_bar.is_none(),
+        };
+
+        let frames_match = self.replay_data.len() == other.replay_data.len()
+            && self
+                .replay_data
+                .iter()
+                .zip(&other.replay_data)
+                .all(|(a, b)| {
+                    let positions_match = match (a, b) {
+                        (ReplayEvent::Osu(a), ReplayEvent::Osu(b)) => {
+                            approx_eq(a.x, b.x) && approx_eq(a.y, b.y)
+                        }
+                        (ReplayEvent::Taiko(a), ReplayEvent::Taiko(b)) => a.x == b.x,
+                        (ReplayEvent::Catch(a), ReplayEvent::Catch(b)) => approx_eq(a.x, b.x),
+                        (ReplayEvent::Mania(_), ReplayEvent::Mania(_)) => true,
+                        _ => false,
+                    };
+                    positions_match
+                        && a.time_delta() == b.time_delta()
+                        && a.key_bits() == b.key_bits()
+                });
+
+        Ok(metadata_matches && life_bar_matches && frames_match)
+    }
+
+    /// Returns the target practice accuracy as it is written to `.osr` files.
+    ///
+    /// The value is only written with the Target Practice mod, and defaults to
+    /// 0 when the mod is set without a value.
+    pub(crate) fn written_target_practice(&self) -> Option<f64> {
+        self.mods
+            .contains(Mod::TARGET)
+            .then(|| self.target_practice.unwrap_or(0.0))
+    }
+
+    /// Returns the lazer data block as it is written to `.osr` files.
+    ///
+    /// The block is only written for lazer replays. `mania_settings` replaces
+    /// the `mania_settings` key of `lazer_data`, or removes it when `None`.
+    pub(crate) fn written_lazer_data(&self) -> Option<serde_json::Value> {
+        if self.game_version < LAZER_GAME_VERSION {
+            return None;
+        }
+
+        let mut data = self.lazer_data.clone();
+        match &self.mania_settings {
+            Some(settings) => {
+                let data = data.get_or_insert_with(|| serde_json::json!({}));
+                if let Some(object) = data.as_object_mut() {
+                    object.insert("mania_settings".to_string(), serde_json::json!(settings));
+                }
+            }
+            None => {
+                if let Some(object) = data.as_mut().and_then(serde_json::Value::as_object_mut) {
+                    object.remove("mania_settings");
+                }
+            }
+        }
+
+        data
+    }
+
+    /// Returns the size of the compressed frame block of this replay.
+    ///
+    /// Only the frame block is compressed, and the compressed bytes are
+    /// discarded, which is cheaper than a full `pack` when only the size is needed.
+    ///
+    /// # Returns
+    ///
+    /// The length in bytes of the frame block `pack` would write
+    pub fn compressed_frame_size(&self) -> Result<usize, ReplayError> {
+        let packer = Packer::new();
+        let data = packer.format_replay_data(&self.replay_data, self.rng_seed)?;
+        Ok(packer.compress(data.as_bytes())?.len())
+    }
+
+    /// Returns how many frames fit in each kilobyte of the compressed frame block.
+    ///
+    /// Repetitive input, such as a cursor resting in place, compresses well
+    /// and reports a high value; erratic movement reports a low one.
+    ///
+    /// # Returns
+    ///
+    /// The number of frames per 1024 compressed bytes, see
+    /// [`Replay::compressed_frame_size`]
+    pub fn frames_per_kb(&self) -> Result<f64, ReplayError> {
+        let size = self.compressed_frame_size()?;
+        Ok(self.replay_data.len() as f64 / (size as f64 / 1024.0))
+    }
+
     /// Returns the bytes representing this `Replay`, in `.osr` format, with custom packer settings.
     ///
     /// # Arguments
@@ -153,6 +514,38 @@ impl Replay {
         packer.pack(self)
     }
 
+    /// Returns the uncompressed frame block of this `Replay`.
+    ///
+    /// This is the exact string `pack` compresses with LZMA: every frame as
+    /// `time_delta|x|y|keys,`, followed by the rng seed frame when present.
+    ///
+    /// # Returns
+    ///
+    /// The frame block before compression
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReplayError::InvalidFormat` if a frame formats with stray separators,
+    /// see [`Packer::check_frame`]
+    pub fn replay_data_string(&self) -> Result<String, ReplayError> {
+        Packer::new().format_replay_data(&self.replay_data, self.rng_seed)
+    }
+
+    /// Returns the bytes representing this `Replay`, in `.osr` format, with the given packer options.
+    ///
+    /// This is a shorthand for building a `Packer` from `options` and calling `pack_with`.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The options controlling compression and which fields are written
+    ///
+    /// # Returns
+    ///
+    /// The bytes representing this `Replay`, in `.osr` format
+    pub fn to_osr(&self, options: PackerOptions) -> Result<Vec<u8>, ReplayError> {
+        Packer::with_options(options).pack(self)
+    }
+
     /// Returns the bytes representing this `Replay`, in `.osr` format, without LZMA compression.
     ///
     /// This method is similar to `pack` but saves the replay data in uncompressed format,
@@ -179,6 +572,973 @@ impl Replay {
     pub fn pack_uncompressed_with(&self, packer: &Packer) -> Result<Vec<u8>, ReplayError> {
         packer.pack_uncompressed(self)
     }
+
+    /// Computes the replay hash osu! stable stores for this replay's score fields.
+    ///
+    /// The hash is the MD5 digest of the score checksum osu! stable writes,
+    /// over the hit counts, beatmap hash, max combo, perfect flag, username,
+    /// score, rank, mods and pass state. The rank and pass state aren't stored
+    /// in the replay: the rank is derived with [`Replay::grade`], and a play
+    /// counts as passed unless [`Replay::has_failed`] reports a fail, in which
+    /// case the rank is F.
+    ///
+    /// # Returns
+    ///
+    /// The lowercase hex MD5 digest
+    pub fn compute_replay_hash(&self) -> String {
+        let passed = !self.has_failed().unwrap_or(false);
+        let rank = if !passed {
+            "F"
+        } else {
+            match self.grade() {
+                Grade::SSH => "XH",
+                Grade::SS => "X",
+                Grade::SH => "SH",
+                Grade::S => "S",
+                Grade::A => "A",
+                Grade::B => "B",
+                Grade::C => "C",
+                Grade::D => "D",
+            }
+        };
+
+        let checksum_input = format!(
+            "{}p{}o{}o{}t{}a{}r{}e{}y{}o{}u{}{}{}",
+            self.count_100 as u32 + self.count_300 as u32,
+            self.count_50,
+            self.count_geki,
+            self.count_katu,
+            self.count_miss,
+            self.beatmap_hash,
+            self.max_combo,
+            if self.perfect { "True" } else { "False" },
+            self.username,
+            self.score,
+            rank,
+            self.mods.value(),
+            if passed { "True" } else { "False" }
+        );
+
+        format!("{:x}", md5::compute(checksum_input))
+    }
+
+    /// Sets the beatmap hash and recomputes the replay hash to match.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The new beatmap hash, as 32 hex characters
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReplayError::InvalidFormat` if `hash` isn't an MD5 hex digest,
+    /// in which case the replay is left unchanged.
+    pub fn set_beatmap_hash_and_rehash(&mut self, hash: &str) -> Result<(), ReplayError> {
+        if hash.len() != 32 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ReplayError::InvalidFormat(format!(
+                "Invalid beatmap hash: {:?}",
+                hash
+            )));
+        }
+
+        self.beatmap_hash = hash.to_ascii_lowercase();
+        self.replay_hash = self.compute_replay_hash();
+        Ok(())
+    }
+
+    /// Interprets `game_version` as the `YYYYMMDD` build date osu! writes.
+    ///
+    /// # Returns
+    ///
+    /// The client build date, or `None` if `game_version` isn't a valid date
+    pub fn game_version_date(&self) -> Option<NaiveDate> {
+        let version = self.game_version;
+        NaiveDate::from_ymd_opt((version / 10000) as i32, version / 100 % 100, version % 100)
+    }
+
+    /// Sets `game_version` to the `YYYYMMDD` encoding of the given build date.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - The client build date to target
+    pub fn set_game_version_from_date(&mut self, date: NaiveDate) {
+        self.game_version = date.year() as u32 * 10000 + date.month() * 100 + date.day();
+    }
+
+    /// Formats the timestamp of this replay the way osu! displays replay dates.
+    ///
+    /// The date is written month first, as with osu!'s default English
+    /// locale, followed by the 24-hour UTC time, such as `03/15/2024 18:42:07`.
+    pub fn timestamp_osu_format(&self) -> String {
+        self.timestamp.format("%m/%d/%Y %H:%M:%S").to_string()
+    }
+
+    /// Drops the time of day from the timestamp of this replay, keeping only the date.
+    ///
+    /// This hides when exactly a replay was played while keeping the date,
+    /// for example before publishing a dataset. The timestamp becomes midnight
+    /// UTC of the same day.
+    pub fn truncate_timestamp_to_date(&mut self) {
+        self.timestamp = self
+            .timestamp
+            .date_naive()
+            .and_time(NaiveTime::MIN)
+            .and_utc();
+    }
+
+    /// Drops the sub-second part of the timestamp of this replay.
+    ///
+    /// `.osr` timestamps carry 100-nanosecond ticks that can differ between
+    /// exports of the same play. Snapping to whole seconds makes such exports
+    /// compare equal, and the packer then writes a tick count on a second
+    /// boundary.
+    pub fn snap_timestamp_to_seconds(&mut self) {
+        self.timestamp = self.timestamp.trunc_subsecs(0);
+    }
+
+    /// Fills a missing life bar with a synthetic, always-full one.
+    ///
+    /// The generated life bar has `samples` states at full health (1.0), evenly
+    /// spaced from time 0 to the end of the replay. It carries no information
+    /// about the actual play and only exists so tools that require a life bar
+    /// keep working. Both a missing and an empty life bar are filled, while
+    /// replays that already have life bar states are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The number of life bar states to generate
+    pub fn generate_flat_life_bar(&mut self, samples: usize) {
+        if self
+            .life_bar_graph
+            .as_ref()
+            .is_some_and(|states| !states.is_empty())
+        {
+            return;
+        }
+
+        let duration: i64 = self
+            .replay_data
+            .iter()
+            .map(|event| event.time_delta() as i64)
+            .sum();
+        let duration = duration.max(0);
+        let last = samples.saturating_sub(1).max(1) as i64;
+
+        let states = (0..samples as i64)
+            .map(|i| LifeBarState {
+                time: (duration * i / last) as i32,
+                life: 1.0,
+            })
+            .collect();
+
+        self.life_bar_graph = Some(states);
+    }
+
+    /// Appends the frames of another replay after the frames of this one.
+    ///
+    /// `other` is shifted in time so that its first frame comes one 60 fps
+    /// frame (16ms) after the last frame of this replay; the spacing between
+    /// its own frames is kept. Its life bar states are shifted by the same
+    /// amount and appended to this replay's life bar. Metadata is left as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The replay to append
+    ///
+    /// # Returns
+    ///
+    /// `ReplayError::InvalidFormat` if the replays are of different game modes
+    pub fn append(&mut self, other: &Replay) -> Result<(), ReplayError> {
+        const APPEND_GAP_MS: i64 = 16;
+
+        if self.mode != other.mode {
+            return Err(ReplayError::InvalidFormat(format!(
+                "Cannot append a {:?} replay to a {:?} replay",
+                other.mode, self.mode
+            )));
+        }
+
+        let Some(first) = other.replay_data.first() else {
+            return Ok(());
+        };
+
+        let end: i64 = self
+            .replay_data
+            .iter()
+            .map(|event| event.time_delta() as i64)
+            .sum();
+        let start = if self.replay_data.is_empty() {
+            first.time_delta() as i64
+        } else {
+            end + APPEND_GAP_MS
+        };
+        let shift = start - first.time_delta() as i64;
+
+        let mut first = first.clone();
+        first.set_time_delta((start - end) as i32);
+        self.replay_data.push(first);
+        self.replay_data
+            .extend(other.replay_data.iter().skip(1).cloned());
+
+        if let Some(other_states) = &other.life_bar_graph {
+            let shifted = other_states.iter().map(|state| LifeBarState {
+                time: (state.time as i64 + shift) as i32,
+                life: state.life,
+            });
+            self.life_bar_graph
+                .get_or_insert_with(Vec::new)
+                .extend(shifted);
+        }
+
+        Ok(())
+    }
+
+    /// Drops every frame and life bar state after the given time.
+    ///
+    /// The remaining frames keep their absolute times; when a dropped frame sat
+    /// between two kept ones, the delta of the later frame absorbs it.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_ms` - The latest absolute time to keep, in milliseconds
+    pub fn trim_to_duration(&mut self, max_ms: i64) {
+        let mut time = 0i64;
+        let mut kept_time = 0i64;
+
+        self.replay_data.retain_mut(|event| {
+            time += event.time_delta() as i64;
+            if time > max_ms {
+                return false;
+            }
+
+            event.set_time_delta((time - kept_time) as i32);
+            kept_time = time;
+            true
+        });
+
+        if let Some(states) = &mut self.life_bar_graph {
+            states.retain(|state| state.time as i64 <= max_ms);
+        }
+    }
+
+    /// Thins out the frames of this replay, keeping every `factor`-th frame.
+    ///
+    /// Frames where the pressed keys change and the last frame are always kept,
+    /// so no input is lost. Kept frames keep their absolute times; the delta of
+    /// each kept frame absorbs the frames dropped before it.
+    ///
+    /// # Arguments
+    ///
+    /// * `factor` - Keep one frame out of every `factor`; 0 and 1 keep every frame
+    ///
+    /// # Returns
+    ///
+    /// The original indices of the kept frames, in order, for mapping data
+    /// attached to the original frames onto the decimated ones
+    pub fn decimate(&mut self, factor: usize) -> Vec<usize> {
+        let factor = factor.max(1);
+        let last = self.replay_data.len().saturating_sub(1);
+        let mut retained = Vec::new();
+        let mut index = 0;
+        let mut time = 0i64;
+        let mut kept_time = 0i64;
+        let mut previous_keys = 0u32;
+
+        self.replay_data.retain_mut(|event| {
+            let i = index;
+            index += 1;
+            time += event.time_delta() as i64;
+            let keys = event.key_bits();
+            let keys_changed = keys != previous_keys;
+            previous_keys = keys;
+
+            if i % factor != 0 && i != last && !keys_changed {
+                return false;
+            }
+
+            event.set_time_delta((time - kept_time) as i32);
+            kept_time = time;
+            retained.push(i);
+            true
+        });
+
+        retained
+    }
+
+    /// Returns the frames of this replay.
+    pub fn frames(&self) -> &[ReplayEvent] {
+        &self.replay_data
+    }
+
+    /// Returns the frames of this replay paired with their absolute time.
+    ///
+    /// The time is the running sum of `time_delta` up to and including each
+    /// frame, in milliseconds. Negative deltas, such as those of offset frames,
+    /// are accumulated like any other, so the time can go backwards.
+    pub fn events_with_time(&self) -> impl Iterator<Item = (i64, &ReplayEvent)> {
+        self.replay_data.iter().scan(0i64, |time, event| {
+            *time += event.time_delta() as i64;
+            Some((*time, event))
+        })
+    }
+
+    /// Returns a guard for editing the frames of this replay.
+    ///
+    /// The guard dereferences to the frame list. When it is dropped, the
+    /// timing invariant documented on [`Replay::replay_data`] is restored with
+    /// [`Replay::repair_frame_timing`].
+    pub fn frames_mut_checked(&mut self) -> FramesGuard<'_> {
+        FramesGuard { replay: self }
+    }
+
+    /// Restores the timing invariant of the frames of this replay.
+    ///
+    /// The setup frames osu! writes before gameplay, which are every frame
+    /// before the first positive delta (usually a `0` and a `-1` delta), are
+    /// left alone; any negative delta after them is set to 0.
+    ///
+    /// # Returns
+    ///
+    /// The number of frames that were repaired
+    pub fn repair_frame_timing(&mut self) -> usize {
+        let mut repaired = 0;
+
+        for event in self
+            .replay_data
+            .iter_mut()
+            .skip_while(|event| event.time_delta() <= 0)
+        {
+            if event.time_delta() < 0 {
+                event.set_time_delta(0);
+                repaired += 1;
+            }
+        }
+
+        repaired
+    }
+
+    /// Sorts the life bar states of this replay by time.
+    ///
+    /// States with equal times keep their relative order.
+    pub fn sort_life_bar(&mut self) {
+        if let Some(states) = &mut self.life_bar_graph {
+            states.sort_by_key(|state| state.time);
+        }
+    }
+
+    /// Returns the time-weighted average health over the life bar.
+    ///
+    /// The area under the life curve is integrated with the trapezoidal rule
+    /// and divided by the time the life bar covers, so a player who spent
+    /// long stretches at low health scores lower. The states are expected to
+    /// be sorted by time, see [`Replay::sort_life_bar`].
+    ///
+    /// # Returns
+    ///
+    /// The average health from 0.0 to 1.0, the health of the only state if
+    /// the life bar covers no time, or `None` if there is no life bar
+    pub fn life_bar_area(&self) -> Option<f64> {
+        let states = self.life_bar_graph.as_deref()?;
+        let (first, last) = (states.first()?, states.last()?);
+
+        let duration = last.time as f64 - first.time as f64;
+        if duration <= 0.0 {
+            return Some(first.life as f64);
+        }
+
+        let area: f64 = states
+            .windows(2)
+            .map(|pair| {
+                let width = pair[1].time as f64 - pair[0].time as f64;
+                width * (pair[0].life as f64 + pair[1].life as f64) / 2.0
+            })
+            .sum();
+
+        Some(area / duration)
+    }
+
+    /// Returns the health at a given time, interpolated from the life bar.
+    ///
+    /// The health is linearly interpolated between the states surrounding
+    /// `time_ms`. Times before the first or after the last state take the
+    /// health of that state. The states are expected to be sorted by time,
+    /// see [`Replay::sort_life_bar`].
+    ///
+    /// # Arguments
+    ///
+    /// * `time_ms` - The time to query, in milliseconds
+    ///
+    /// # Returns
+    ///
+    /// The health from 0.0 to 1.0, or `None` if there is no life bar or it
+    /// holds no states
+    pub fn life_at(&self, time_ms: i32) -> Option<f32> {
+        let states = self.life_bar_graph.as_deref()?;
+        let (first, last) = (states.first()?, states.last()?);
+
+        if time_ms <= first.time {
+            return Some(first.life);
+        }
+        if time_ms >= last.time {
+            return Some(last.life);
+        }
+
+        // The first state after `time_ms`; in range thanks to the checks above
+        let next = states.partition_point(|state| state.time <= time_ms);
+        let (before, after) = (&states[next - 1], &states[next]);
+
+        let span = after.time as f64 - before.time as f64;
+        let progress = (time_ms as f64 - before.time as f64) / span;
+        Some(before.life + ((after.life - before.life) as f64 * progress) as f32)
+    }
+
+    /// Returns whether the player failed, judging by the life bar.
+    ///
+    /// A play counts as failed when health reaches 0.0 after the start of the
+    /// replay. States at time 0 or earlier are ignored, as health can read 0.0
+    /// there before the life bar fills up.
+    ///
+    /// # Returns
+    ///
+    /// Whether health ever hit zero, or `None` if there is no life bar or it
+    /// holds no states
+    pub fn has_failed(&self) -> Option<bool> {
+        let states = self.life_bar_graph.as_deref()?;
+        if states.is_empty() {
+            return None;
+        }
+
+        Some(
+            states
+                .iter()
+                .any(|state| state.time > 0 && state.life <= 0.0),
+        )
+    }
+
+    /// Finds the stretches of the life bar without any health data.
+    ///
+    /// The states are expected to be sorted by time, see [`Replay::sort_life_bar`].
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold_ms` - The time between consecutive states above which a gap is reported
+    ///
+    /// # Returns
+    ///
+    /// The `(start, end)` times of each gap, empty if there is no life bar
+    pub fn life_bar_gaps(&self, threshold_ms: i32) -> Vec<(i32, i32)> {
+        let Some(states) = &self.life_bar_graph else {
+            return Vec::new();
+        };
+
+        states
+            .windows(2)
+            .filter(|pair| pair[1].time as i64 - pair[0].time as i64 > threshold_ms as i64)
+            .map(|pair| (pair[0].time, pair[1].time))
+            .collect()
+    }
+
+    /// Returns the frames with the vertical flip Hard Rock applies undone.
+    ///
+    /// Hard Rock flips the playfield upside down, and the recorded frames
+    /// follow the flipped playfield. This maps every osu!standard frame back
+    /// with `y = 384 - y`, so the cursor path can be compared to plays without
+    /// Hard Rock. The replay itself, including its mods, is left untouched.
+    ///
+    /// # Returns
+    ///
+    /// The un-mirrored frames, or `None` if the replay wasn't played with Hard Rock
+    pub fn unmirrored_hr_frames(&self) -> Option<Vec<ReplayEvent>> {
+        if !self.mods.contains(Mod::HARD_ROCK) {
+            return None;
+        }
+
+        let mut frames = self.replay_data.clone();
+        for event in &mut frames {
+            if let ReplayEvent::Osu(event) = event {
+                event.y = 384.0 - event.y;
+            }
+        }
+        Some(frames)
+    }
+
+    /// Clears osu!mania key bits for lanes beyond the beatmap's key count.
+    ///
+    /// Corrupt replays can set bits for lanes that don't exist, which breaks
+    /// per-lane processing. Frames of other modes are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_count` - The number of lanes of the beatmap
+    ///
+    /// # Returns
+    ///
+    /// The number of frames that were altered
+    pub fn clamp_mania_keys(&mut self, key_count: u8) -> usize {
+        let mask = 1u32
+            .checked_shl(key_count as u32)
+            .map_or(u32::MAX, |bit| bit - 1);
+        let mut altered = 0;
+
+        for event in &mut self.replay_data {
+            if let ReplayEvent::Mania(event) = event {
+                if event.keys.value() & !mask != 0 {
+                    event.keys = KeyMania(event.keys.value() & mask);
+                    altered += 1;
+                }
+            }
+        }
+
+        altered
+    }
+
+    /// Returns the osu!mania lane count forced by this replay's key mod.
+    ///
+    /// With co-op (`KEY_COOP`) two players share the keyboard and each half
+    /// gets the key mod's lane count, so a 4K co-op replay reports 8 lanes.
+    ///
+    /// # Returns
+    ///
+    /// The number of lanes, or None if no key mod is set
+    pub fn mania_key_count(&self) -> Option<u8> {
+        let mods = self.effective_mods();
+        let keys = mods.mania_keys?;
+
+        Some(if mods.key_coop { keys * 2 } else { keys })
+    }
+
+    /// Returns whether this replay holds no actual play.
+    ///
+    /// A replay is empty when no frame holds a key and every hit count is
+    /// zero, as left behind by abandoned plays or spectator artifacts.
+    pub fn is_empty_play(&self) -> bool {
+        let no_hits = [
+            self.count_300,
+            self.count_100,
+            self.count_50,
+            self.count_geki,
+            self.count_katu,
+            self.count_miss,
+        ]
+        .iter()
+        .all(|&count| count == 0);
+
+        no_hits && self.replay_data.iter().all(|event| event.key_bits() == 0)
+    }
+
+    /// Returns the key bits of the mode's key set that no frame ever sets.
+    ///
+    /// The key set is M1, M2, K1 and K2 for osu!standard (smoke is not a
+    /// gameplay key), the four drum keys for osu!taiko, the dash bit for
+    /// osu!catch, and the lanes from `mania_key_count` for osu!mania, or all
+    /// 18 lanes when no key mod is set. Stable sets the matching mouse bit
+    /// alongside K1 and K2, so M1 and M2 only show up when neither was used.
+    ///
+    /// # Returns
+    ///
+    /// The bitmask of unused keys, in the mode's key layout
+    pub fn unused_keys(&self) -> u32 {
+        let full = match self.mode {
+            GameMode::Std => (Key::M1 | Key::M2 | Key::K1 | Key::K2).value(),
+            GameMode::Taiko => (KeyTaiko::LEFT_DON
+                | KeyTaiko::LEFT_KAT
+                | KeyTaiko::RIGHT_DON
+                | KeyTaiko::RIGHT_KAT)
+                .value(),
+            GameMode::Catch => 1,
+            GameMode::Mania => {
+                let lanes = self.mania_key_count().unwrap_or(18).min(18);
+                (1u32 << lanes) - 1
+            }
+        };
+
+        let used = self
+            .replay_data
+            .iter()
+            .fold(0, |used, event| used | event.key_bits());
+
+        full & !used
+    }
+
+    /// Returns the hit counts of this replay under their osu! API v2 names.
+    ///
+    /// The counts are mapped as-is: 300s, 100s, 50s and misses become great,
+    /// ok, meh and miss, while gekis become perfect and katus good.
+    pub fn to_statistics(&self) -> Statistics {
+        Statistics {
+            great: self.count_300,
+            ok: self.count_100,
+            meh: self.count_50,
+            miss: self.count_miss,
+            perfect: self.count_geki,
+            good: self.count_katu,
+        }
+    }
+
+    /// Returns the number of judgements recorded in this replay.
+    ///
+    /// The hit counts mean different things per mode, so the sum is mode-aware:
+    ///
+    /// * osu!standard and osu!taiko: 300s, 100s, 50s and misses (gekis and katus
+    ///   are subsets of the 300s and 100s)
+    /// * osu!catch: fruits (300s), drops (100s), droplets (50s), missed droplets
+    ///   (katus) and misses
+    /// * osu!mania: MAX (gekis), 300s, 200s (katus), 100s, 50s and misses
+    pub fn judged_objects(&self) -> u32 {
+        let misses = match self.mode {
+            GameMode::Catch => self.count_miss as u32 + self.count_katu as u32,
+            _ => self.count_miss as u32,
+        };
+
+        self.successful_hits() + misses
+    }
+
+    /// Returns the number of judgements in this replay that weren't misses.
+    ///
+    /// This is [`Replay::judged_objects`] without the misses (and, in
+    /// osu!catch, without the missed droplets).
+    pub fn successful_hits(&self) -> u32 {
+        let hits = self.count_300 as u32 + self.count_100 as u32 + self.count_50 as u32;
+
+        match self.mode {
+            GameMode::Mania => hits + self.count_geki as u32 + self.count_katu as u32,
+            _ => hits,
+        }
+    }
+
+    /// Returns the score of this replay widened to 64 bits.
+    ///
+    /// Useful when summing or comparing scores, where the `u32` field could
+    /// overflow.
+    pub fn score_i64(&self) -> i64 {
+        self.score as i64
+    }
+
+    /// Returns whether `max_combo` fits within the judgements of this replay.
+    ///
+    /// In osu!taiko and osu!catch every combo increment comes with a
+    /// judgement, so a combo above [`Replay::judged_objects`] can only come from
+    /// an edited score. osu!standard slider ticks and ends and osu!mania hold
+    /// note ticks add combo without a hit count of their own, so the counts
+    /// don't bound the combo there and those modes are always plausible.
+    pub fn combo_plausible(&self) -> bool {
+        match self.mode {
+            GameMode::Taiko | GameMode::Catch => self.max_combo as u32 <= self.judged_objects(),
+            GameMode::Std | GameMode::Mania => true,
+        }
+    }
+
+    /// Returns the accuracy of this replay, as shown by osu!.
+    ///
+    /// Each mode weighs the hit counts differently:
+    ///
+    /// * osu!standard: 300s, 100s and 50s are worth 300, 100 and 50
+    /// * osu!taiko: GREATs (300s) are worth a full hit and GOODs (100s) half
+    /// * osu!catch: the fraction of fruits, drops and droplets caught
+    /// * osu!mania: MAX (gekis) and 300s are worth 300, 200s (katus) 200,
+    ///   100s 100 and 50s 50
+    ///
+    /// # Returns
+    ///
+    /// The accuracy as a percentage from 0.0 to 100.0, or 100.0 if nothing
+    /// was judged (as osu! shows for an empty play)
+    pub fn accuracy(&self) -> f64 {
+        let judged = self.judged_objects();
+        if judged == 0 {
+            return 100.0;
+        }
+
+        let count_300 = self.count_300 as f64;
+        let count_100 = self.count_100 as f64;
+        let count_50 = self.count_50 as f64;
+        let judged = judged as f64;
+
+        let accuracy = match self.mode {
+            GameMode::Std => {
+                (300.0 * count_300 + 100.0 * count_100 + 50.0 * count_50) / (300.0 * judged)
+            }
+            GameMode::Taiko => (count_300 + 0.5 * count_100) / judged,
+            GameMode::Catch => self.successful_hits() as f64 / judged,
+            GameMode::Mania => {
+                (300.0 * (self.count_geki as f64 + count_300)
+                    + 200.0 * self.count_katu as f64
+                    + 100.0 * count_100
+                    + 50.0 * count_50)
+                    / (300.0 * judged)
+            }
+        };
+
+        accuracy * 100.0
+    }
+
+    /// Returns the letter grade of this replay, following the stable client's rules.
+    ///
+    /// * osu!standard and osu!taiko grade by the share of 300s: SS for only
+    ///   300s, S above 90% with at most 1% 50s and no miss, A above 80% without
+    ///   a miss or above 90%, B above 70% without a miss or above 80%, C above
+    ///   60%, and D otherwise
+    /// * osu!catch grades by accuracy: SS at 100%, then S, A, B and C above
+    ///   98%, 94%, 90% and 85%
+    /// * osu!mania grades by accuracy: SS at 100%, then S, A, B and C above
+    ///   95%, 90%, 80% and 70%
+    ///
+    /// Hidden or Flashlight (and Fade In in osu!mania) turn SS into SSH and S
+    /// into SH. A replay without any judgement is graded D.
+    pub fn grade(&self) -> Grade {
+        let judged = self.judged_objects();
+        if judged == 0 {
+            return Grade::D;
+        }
+
+        let grade = match self.mode {
+            GameMode::Std | GameMode::Taiko => {
+                let ratio_300 = self.count_300 as f64 / judged as f64;
+                let ratio_50 = self.count_50 as f64 / judged as f64;
+                let no_miss = self.count_miss == 0;
+
+                if ratio_300 == 1.0 {
+                    Grade::SS
+                } else if ratio_300 > 0.9 && ratio_50 <= 0.01 && no_miss {
+                    Grade::S
+                } else if (ratio_300 > 0.8 && no_miss) || ratio_300 > 0.9 {
+                    Grade::A
+                } else if (ratio_300 > 0.7 && no_miss) || ratio_300 > 0.8 {
+                    Grade::B
+                } else if ratio_300 > 0.6 {
+                    Grade::C
+                } else {
+                    Grade::D
+                }
+            }
+            GameMode::Catch | GameMode::Mania => {
+                let accuracy = self.accuracy();
+                let thresholds = if self.mode == GameMode::Catch {
+                    [98.0, 94.0, 90.0, 85.0]
+                } else {
+                    [95.0, 90.0, 80.0, 70.0]
+                };
+
+                if accuracy >= 100.0 {
+                    Grade::SS
+                } else if accuracy > thresholds[0] {
+                    Grade::S
+                } else if accuracy > thresholds[1] {
+                    Grade::A
+                } else if accuracy > thresholds[2] {
+                    Grade::B
+                } else if accuracy > thresholds[3] {
+                    Grade::C
+                } else {
+                    Grade::D
+                }
+            }
+        };
+
+        let silver = self.mods.contains(Mod::HIDDEN)
+            || self.mods.contains(Mod::FLASHLIGHT)
+            || (self.mode == GameMode::Mania && self.mods.contains(Mod::FADE_IN));
+        match grade {
+            Grade::SS if silver => Grade::SSH,
+            Grade::S if silver => Grade::SH,
+            grade => grade,
+        }
+    }
+
+    /// Returns the accuracy of this replay under a custom weighting of the hit counts.
+    ///
+    /// This allows scoring schemes other than the classic one, such as giving
+    /// osu!mania MAX judgements more weight than 300s.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - The weight of each hit count
+    ///
+    /// # Returns
+    ///
+    /// The accuracy as a percentage, or 100.0 if the weighted total is zero
+    pub fn accuracy_with(&self, weights: AccuracyWeights) -> f64 {
+        let counts = [
+            (self.count_300, weights.count_300),
+            (self.count_100, weights.count_100),
+            (self.count_50, weights.count_50),
+            (self.count_geki, weights.count_geki),
+            (self.count_katu, weights.count_katu),
+            (self.count_miss, weights.count_miss),
+        ];
+
+        let (earned, possible) =
+            counts
+                .iter()
+                .fold((0.0, 0.0), |(earned, possible), &(count, weight)| {
+                    (
+                        earned + count as f64 * weight.numerator,
+                        possible + count as f64 * weight.denominator,
+                    )
+                });
+
+        if possible == 0.0 {
+            return 100.0;
+        }
+
+        earned / possible * 100.0
+    }
+
+    /// Returns whether this replay was played with No Fail.
+    pub fn has_no_fail(&self) -> bool {
+        self.mods.contains(Mod::NO_FAIL)
+    }
+
+    /// Returns whether this replay was played with Easy.
+    pub fn has_easy(&self) -> bool {
+        self.mods.contains(Mod::EASY)
+    }
+
+    /// Returns whether this replay was played with Hidden.
+    pub fn has_hidden(&self) -> bool {
+        self.mods.contains(Mod::HIDDEN)
+    }
+
+    /// Returns whether this replay was played with Hard Rock.
+    pub fn has_hard_rock(&self) -> bool {
+        self.mods.contains(Mod::HARD_ROCK)
+    }
+
+    /// Returns whether this replay was played with Sudden Death.
+    pub fn has_sudden_death(&self) -> bool {
+        self.mods.contains(Mod::SUDDEN_DEATH)
+    }
+
+    /// Returns whether this replay was played with Double Time.
+    pub fn has_double_time(&self) -> bool {
+        self.mods.contains(Mod::DOUBLE_TIME)
+    }
+
+    /// Returns whether this replay was played with Relax.
+    pub fn has_relax(&self) -> bool {
+        self.mods.contains(Mod::RELAX)
+    }
+
+    /// Returns whether this replay was played with Half Time.
+    pub fn has_half_time(&self) -> bool {
+        self.mods.contains(Mod::HALF_TIME)
+    }
+
+    /// Returns whether this replay was played with Nightcore.
+    pub fn has_nightcore(&self) -> bool {
+        self.mods.contains(Mod::NIGHTCORE)
+    }
+
+    /// Returns whether this replay was played with Flashlight.
+    pub fn has_flashlight(&self) -> bool {
+        self.mods.contains(Mod::FLASHLIGHT)
+    }
+
+    /// Returns whether this replay was played with Spun Out.
+    pub fn has_spun_out(&self) -> bool {
+        self.mods.contains(Mod::SPUN_OUT)
+    }
+
+    /// Returns whether this replay was played with Autopilot.
+    pub fn has_autopilot(&self) -> bool {
+        self.mods.contains(Mod::AUTOPILOT)
+    }
+
+    /// Returns whether this replay was played with Perfect.
+    pub fn has_perfect(&self) -> bool {
+        self.mods.contains(Mod::PERFECT)
+    }
+
+    /// Returns whether this replay was played with ScoreV2.
+    pub fn has_score_v2(&self) -> bool {
+        self.mods.contains(Mod::SCORE_V2)
+    }
+
+    /// Returns the mods of this replay as explicit booleans.
+    ///
+    /// See [`EffectiveMods`] for how Nightcore and Perfect are resolved.
+    pub fn effective_mods(&self) -> EffectiveMods {
+        EffectiveMods::from(self.mods)
+    }
+
+    /// Returns whether this is a Cinema replay.
+    ///
+    /// Cinema replays are recorded without any player input, so they have the
+    /// `CINEMA` mod set and no frames.
+    pub fn is_cinema(&self) -> bool {
+        self.mods.contains(Mod::CINEMA) && self.replay_data.is_empty()
+    }
+
+    /// Returns a one-line description of this replay for logs and CLI output.
+    ///
+    /// The line holds the username, mode, mods, score, accuracy, max combo and
+    /// play date, such as `TestPlayer | osu! | HDHR | 1,000,000 | 98.76% | 150x | 2024-01-01`.
+    pub fn summary(&self) -> String {
+        let mode = match self.mode {
+            GameMode::Std => "osu!",
+            GameMode::Taiko => "osu!taiko",
+            GameMode::Catch => "osu!catch",
+            GameMode::Mania => "osu!mania",
+        };
+
+        let digits = self.score.to_string();
+        let mut score = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                score.push(',');
+            }
+            score.push(digit);
+        }
+
+        format!(
+            "{} | {} | {} | {} | {:.2}% | {}x | {}",
+            self.username,
+            mode,
+            self.mods,
+            score,
+            self.accuracy(),
+            self.max_combo,
+            self.timestamp.format("%Y-%m-%d")
+        )
+    }
+
+    /// Returns the mode byte this replay was read with.
+    ///
+    /// Unlike `mode`, this preserves mode bytes outside of 0-3, which can
+    /// point to a corrupt or unusual replay.
+    ///
+    /// # Returns
+    ///
+    /// The byte read from the file, or the byte of `mode` if this replay
+    /// wasn't parsed from a file
+    pub fn raw_mode_byte(&self) -> u8 {
+        self.mode_byte.unwrap_or(self.mode as u8)
+    }
+
+    /// Returns the rng seed of this replay as the unsigned value stored in the file.
+    ///
+    /// # Returns
+    ///
+    /// The rng seed without sign reinterpretation, or `None` if not present
+    pub fn rng_seed_u32(&self) -> Option<u32> {
+        self.rng_seed.map(|seed| seed as u32)
+    }
+}
+
+impl<'a> IntoIterator for &'a Replay {
+    type Item = &'a ReplayEvent;
+    type IntoIter = std::slice::Iter<'a, ReplayEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.replay_data.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Replay {
+    type Item = &'a mut ReplayEvent;
+    type IntoIter = std::slice::IterMut<'a, ReplayEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.replay_data.iter_mut()
+    }
 }
 
 /// Parses the replay data portion of a replay from a string.
@@ -201,6 +1561,41 @@ pub fn parse_replay_data(
     decoded: bool,
     decompressed: bool,
     mode: GameMode,
+) -> Result<Vec<ReplayEvent>, ReplayError> {
+    parse_replay_data_inner(data_string, decoded, decompressed, mode, false)
+}
+
+/// Parses the replay data portion of a replay from a string, rejecting malformed frames.
+///
+/// This behaves like [`parse_replay_data`], except that a non-empty frame which
+/// doesn't have exactly 4 `|`-separated parts returns `ReplayError::InvalidFormat`
+/// instead of being silently skipped.
+///
+/// # Arguments
+///
+/// * `data_string` - The replay data to parse
+/// * `decoded` - Whether `data_string` has already been decoded from a base64 representation
+/// * `decompressed` - Whether `data_string` has already been decompressed from lzma and decoded to ascii
+/// * `mode` - What mode to parse the replay data as
+///
+/// # Returns
+///
+/// The parsed replay events
+pub fn parse_replay_data_strict(
+    data_string: &[u8],
+    decoded: bool,
+    decompressed: bool,
+    mode: GameMode,
+) -> Result<Vec<ReplayEvent>, ReplayError> {
+    parse_replay_data_inner(data_string, decoded, decompressed, mode, true)
+}
+
+fn parse_replay_data_inner(
+    data_string: &[u8],
+    decoded: bool,
+    decompressed: bool,
+    mode: GameMode,
+    strict: bool,
 ) -> Result<Vec<ReplayEvent>, ReplayError> {
     let data = if !decoded && !decompressed {
         general_purpose::STANDARD
@@ -217,7 +1612,35 @@ pub fn parse_replay_data(
     };
 
     let data_string = String::from_utf8(decompressed_data)?;
-    let (replay_data, _) = Unpacker::<Cursor<&[u8]>>::parse_replay_data(&data_string, mode)?;
+    let (replay_data, _) =
+        Unpacker::<Cursor<&[u8]>>::parse_replay_data_with(&data_string, mode, strict)?;
 
     Ok(replay_data)
 }
+
+/// A mutable view of a replay's frames that repairs their timing on drop.
+///
+/// Created by [`Replay::frames_mut_checked`].
+pub struct FramesGuard<'a> {
+    replay: &'a mut Replay,
+}
+
+impl Deref for FramesGuard<'_> {
+    type Target = Vec<ReplayEvent>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.replay.replay_data
+    }
+}
+
+impl DerefMut for FramesGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.replay.replay_data
+    }
+}
+
+impl Drop for FramesGuard<'_> {
+    fn drop(&mut self) {
+        self.replay.repair_frame_timing();
+    }
+}
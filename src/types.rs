@@ -3,10 +3,17 @@
 //! This module defines all the data structures used to represent osu! replay information,
 //! including game modes, mods, key states, and replay events for different game modes.
 
-use serde::{Deserialize, Serialize};
+use crate::error::ReplayError;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
 
 /// Represents the different game modes in osu!
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// With serde, a mode is written as its osu-web ruleset short name (`"osu"`,
+/// `"taiko"`, `"fruits"` or `"mania"`). Deserializing also accepts the mode
+/// number and the variant name. Non-human-readable formats such as bincode
+/// store the mode number instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameMode {
     /// osu!standard - Traditional circle-clicking mode
     Std = 0,
@@ -18,6 +25,10 @@ pub enum GameMode {
     Mania = 3,
 }
 
+/// Converts a mode byte lossily: values outside 0-3 default to `Std`.
+///
+/// Use [`GameMode::try_from_u8`] to reject such values instead. Because of
+/// this impl, `GameMode::try_from(u8)` is the infallible blanket impl.
 impl From<u8> for GameMode {
     fn from(value: u8) -> Self {
         match value {
@@ -30,6 +41,144 @@ impl From<u8> for GameMode {
     }
 }
 
+impl GameMode {
+    /// Converts a mode byte, rejecting values outside 0-3.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The mode byte
+    ///
+    /// # Returns
+    ///
+    /// The game mode, or `ReplayError::InvalidFormat` for an unknown byte
+    pub fn try_from_u8(value: u8) -> Result<Self, ReplayError> {
+        match value {
+            0..=3 => Ok(GameMode::from(value)),
+            _ => Err(ReplayError::InvalidFormat(format!(
+                "Unknown game mode: {}",
+                value
+            ))),
+        }
+    }
+
+    /// Returns the osu-web ruleset short name of this mode.
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            GameMode::Std => "osu",
+            GameMode::Taiko => "taiko",
+            GameMode::Catch => "fruits",
+            GameMode::Mania => "mania",
+        }
+    }
+
+    /// Looks up a mode by its osu-web ruleset short name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The short name, such as `"fruits"`
+    ///
+    /// # Returns
+    ///
+    /// The matching mode, or `None` if the name is unknown
+    pub fn from_short_name(name: &str) -> Option<Self> {
+        match name {
+            "osu" => Some(GameMode::Std),
+            "taiko" => Some(GameMode::Taiko),
+            "fruits" => Some(GameMode::Catch),
+            "mania" => Some(GameMode::Mania),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameMode {
+    /// Formats the mode as its osu-web ruleset short name, such as `fruits`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.short_name())
+    }
+}
+
+impl std::str::FromStr for GameMode {
+    type Err = ReplayError;
+
+    /// Parses a mode from its ruleset short name, mode number or a common alias.
+    ///
+    /// Accepts, in any case, `osu`/`std`/`standard`, `taiko`,
+    /// `fruits`/`catch`/`ctb` and `mania`, as well as `0` to `3`.
+    ///
+    /// # Returns
+    ///
+    /// The matching mode, or `ReplayError::Parse` for an unknown string
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "osu" | "std" | "standard" | "0" => Ok(GameMode::Std),
+            "taiko" | "1" => Ok(GameMode::Taiko),
+            "fruits" | "catch" | "ctb" | "2" => Ok(GameMode::Catch),
+            "mania" | "3" => Ok(GameMode::Mania),
+            _ => Err(ReplayError::Parse(format!("Unknown game mode: {}", s))),
+        }
+    }
+}
+
+impl Serialize for GameMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.short_name())
+        } else {
+            serializer.serialize_u8(*self as u8)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GameMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct GameModeVisitor;
+
+        impl de::Visitor<'_> for GameModeVisitor {
+            type Value = GameMode;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a ruleset short name or a mode number from 0 to 3")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<GameMode, E> {
+                GameMode::from_short_name(value)
+                    .or(match value {
+                        // The variant names written by earlier versions
+                        "Std" => Some(GameMode::Std),
+                        "Taiko" => Some(GameMode::Taiko),
+                        "Catch" => Some(GameMode::Catch),
+                        "Mania" => Some(GameMode::Mania),
+                        _ => None,
+                    })
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<GameMode, E> {
+                match value {
+                    0..=3 => Ok(GameMode::from(value as u8)),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(value), &self)),
+                }
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<GameMode, E> {
+                match u64::try_from(value) {
+                    Ok(value) => self.visit_u64(value),
+                    Err(_) => Err(E::invalid_value(de::Unexpected::Signed(value), &self)),
+                }
+            }
+        }
+
+        // Binary formats such as bincode and postcard can't infer the type
+        // from the input, so only ask for any value where it is self-describing
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(GameModeVisitor)
+        } else {
+            deserializer.deserialize_u8(GameModeVisitor)
+        }
+    }
+}
+
 /// Represents osu! mods as a bitflag integer.
 ///
 /// Mods can be combined using bitwise OR operations.
@@ -81,6 +230,27 @@ impl Mod {
     pub const SCORE_V2: Self = Self(1 << 29);
     pub const MIRROR: Self = Self(1 << 30);
 
+    const KNOWN_MASK: u32 = (Self::MIRROR.0 << 1) - 1;
+
+    /// Returns an empty mod combination, to be extended with [`Mod::with`].
+    pub const fn none() -> Self {
+        Self::NO_MOD
+    }
+
+    /// Returns these mods with `other` added.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rosu_replay::Mod;
+    ///
+    /// let mods = Mod::none().with(Mod::HIDDEN).with(Mod::HARD_ROCK);
+    /// assert!(mods.contains(Mod::HIDDEN) && mods.contains(Mod::HARD_ROCK));
+    /// ```
+    pub const fn with(self, other: Mod) -> Self {
+        Self(self.0 | other.0)
+    }
+
     pub fn contains(&self, other: Self) -> bool {
         (self.0 & other.0) == other.0
     }
@@ -88,6 +258,303 @@ impl Mod {
     pub fn value(&self) -> u32 {
         self.0
     }
+
+    /// Parses mods from an osu! API v2 acronym array, such as `["HD", "NC"]`.
+    ///
+    /// Nightcore and Perfect also set the Double Time and Sudden Death bits,
+    /// matching how osu! writes them to replays.
+    ///
+    /// # Arguments
+    ///
+    /// * `mods` - The mod acronyms, in any case
+    ///
+    /// # Returns
+    ///
+    /// The combined mods, or `ReplayError::Parse` for an unknown acronym
+    pub fn from_api_v2(mods: &[&str]) -> Result<Mod, ReplayError> {
+        let mut value = 0;
+
+        for acronym in mods {
+            let (mod_flag, _) = MOD_ACRONYMS
+                .iter()
+                .find(|(_, name)| name.eq_ignore_ascii_case(acronym))
+                .ok_or_else(|| ReplayError::Parse(format!("Unknown mod acronym: {}", acronym)))?;
+
+            value |= mod_flag.0;
+        }
+
+        Ok(Mod(value).normalized())
+    }
+
+    /// Returns the playback rate implied by these mods.
+    ///
+    /// Double Time and Nightcore play at 1.5x and Half Time at 0.75x. The
+    /// client never sets a speed-up and a slow-down together; if both bits
+    /// are present anyway, the speed-up wins. Divide a time on the beatmap's
+    /// clock by this rate to get the real time that passed during play.
+    ///
+    /// # Returns
+    ///
+    /// 1.5, 0.75 or 1.0
+    pub fn speed_multiplier(&self) -> f64 {
+        if self.contains(Mod::DOUBLE_TIME) || self.contains(Mod::NIGHTCORE) {
+            1.5
+        } else if self.contains(Mod::HALF_TIME) {
+            0.75
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns each mod set in these mods, in ascending bit order.
+    ///
+    /// Every set bit is yielded as its own `Mod`, including the Double Time and
+    /// Sudden Death bits set alongside Nightcore and Perfect, and bits with no
+    /// known mod, so no information is lost.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rosu_replay::Mod;
+    ///
+    /// let mods = Mod::HIDDEN.with(Mod::HARD_ROCK);
+    /// assert_eq!(mods.iter().collect::<Vec<_>>(), vec![Mod::HIDDEN, Mod::HARD_ROCK]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = Mod> {
+        let value = self.0;
+        (0..u32::BITS)
+            .map(|bit| 1u32 << bit)
+            .filter(move |flag| value & flag != 0)
+            .map(Mod)
+    }
+
+    /// Returns the bits of these mods that correspond to a known mod.
+    ///
+    /// # Returns
+    ///
+    /// The mod value with every bit above `MIRROR` cleared
+    pub fn known_bits(&self) -> u32 {
+        self.0 & Self::KNOWN_MASK
+    }
+
+    /// Returns the bits of these mods that don't correspond to any known mod.
+    ///
+    /// Mods are stored as their raw value, so such bits survive parsing and
+    /// packing. A non-zero result means the replay was written by a client
+    /// with mods this crate doesn't know about yet.
+    ///
+    /// # Returns
+    ///
+    /// The mod value with every known bit cleared
+    pub fn unknown_bits(&self) -> u32 {
+        self.0 & !Self::KNOWN_MASK
+    }
+
+    /// Returns these mods with the bits implied by Nightcore and Perfect set.
+    ///
+    /// osu! writes Nightcore with Double Time and Perfect with Sudden Death,
+    /// but some client versions store only the former. Normalizing makes such
+    /// mods compare equal.
+    pub fn normalized(self) -> Mod {
+        let mut value = self.0;
+        if self.contains(Mod::NIGHTCORE) {
+            value |= Mod::DOUBLE_TIME.0;
+        }
+        if self.contains(Mod::PERFECT) {
+            value |= Mod::SUDDEN_DEATH.0;
+        }
+
+        Mod(value)
+    }
+
+    /// Returns these mods as an osu! API v2 acronym array, such as `["HD", "NC"]`.
+    ///
+    /// Nightcore and Perfect are listed without the Double Time and Sudden
+    /// Death bits osu! sets with them.
+    pub fn to_api_v2(&self) -> Vec<String> {
+        self.acronyms(&MOD_ACRONYMS).map(str::to_string).collect()
+    }
+
+    fn acronyms(self, table: &'static [(Mod, &'static str)]) -> impl Iterator<Item = &'static str> {
+        table
+            .iter()
+            .filter(move |&&(mod_flag, _)| {
+                let folded = (mod_flag == Mod::DOUBLE_TIME && self.contains(Mod::NIGHTCORE))
+                    || (mod_flag == Mod::SUDDEN_DEATH && self.contains(Mod::PERFECT));
+                self.contains(mod_flag) && !folded
+            })
+            .map(|&(_, acronym)| acronym)
+    }
+}
+
+/// The acronym of each mod, ordered by bit.
+const MOD_ACRONYMS: [(Mod, &str); 31] = [
+    (Mod::NO_FAIL, "NF"),
+    (Mod::EASY, "EZ"),
+    (Mod::TOUCH_DEVICE, "TD"),
+    (Mod::HIDDEN, "HD"),
+    (Mod::HARD_ROCK, "HR"),
+    (Mod::SUDDEN_DEATH, "SD"),
+    (Mod::DOUBLE_TIME, "DT"),
+    (Mod::RELAX, "RX"),
+    (Mod::HALF_TIME, "HT"),
+    (Mod::NIGHTCORE, "NC"),
+    (Mod::FLASHLIGHT, "FL"),
+    (Mod::AUTOPLAY, "AT"),
+    (Mod::SPUN_OUT, "SO"),
+    (Mod::AUTOPILOT, "AP"),
+    (Mod::PERFECT, "PF"),
+    (Mod::KEY4, "4K"),
+    (Mod::KEY5, "5K"),
+    (Mod::KEY6, "6K"),
+    (Mod::KEY7, "7K"),
+    (Mod::KEY8, "8K"),
+    (Mod::FADE_IN, "FI"),
+    (Mod::RANDOM, "RD"),
+    (Mod::CINEMA, "CN"),
+    (Mod::TARGET, "TP"),
+    (Mod::KEY9, "9K"),
+    (Mod::KEY_COOP, "DS"),
+    (Mod::KEY1, "1K"),
+    (Mod::KEY3, "3K"),
+    (Mod::KEY2, "2K"),
+    (Mod::SCORE_V2, "SV2"),
+    (Mod::MIRROR, "MR"),
+];
+
+/// The acronym of each mod, in the order the stable client displays them.
+const MOD_DISPLAY_ACRONYMS: [(Mod, &str); 31] = [
+    (Mod::EASY, "EZ"),
+    (Mod::NO_FAIL, "NF"),
+    (Mod::HALF_TIME, "HT"),
+    (Mod::HIDDEN, "HD"),
+    (Mod::DOUBLE_TIME, "DT"),
+    (Mod::NIGHTCORE, "NC"),
+    (Mod::HARD_ROCK, "HR"),
+    (Mod::SUDDEN_DEATH, "SD"),
+    (Mod::PERFECT, "PF"),
+    (Mod::FLASHLIGHT, "FL"),
+    (Mod::FADE_IN, "FI"),
+    (Mod::RELAX, "RX"),
+    (Mod::AUTOPILOT, "AP"),
+    (Mod::SPUN_OUT, "SO"),
+    (Mod::TOUCH_DEVICE, "TD"),
+    (Mod::AUTOPLAY, "AT"),
+    (Mod::CINEMA, "CN"),
+    (Mod::TARGET, "TP"),
+    (Mod::SCORE_V2, "SV2"),
+    (Mod::RANDOM, "RD"),
+    (Mod::MIRROR, "MR"),
+    (Mod::KEY_COOP, "DS"),
+    (Mod::KEY1, "1K"),
+    (Mod::KEY2, "2K"),
+    (Mod::KEY3, "3K"),
+    (Mod::KEY4, "4K"),
+    (Mod::KEY5, "5K"),
+    (Mod::KEY6, "6K"),
+    (Mod::KEY7, "7K"),
+    (Mod::KEY8, "8K"),
+    (Mod::KEY9, "9K"),
+];
+
+impl fmt::Display for Mod {
+    /// Formats the mods as concatenated acronyms, such as `HDHR`, or `NM` for no mods.
+    ///
+    /// Acronyms follow the order the stable client displays mods in: the
+    /// difficulty reductions (`EZ`, `NF`, `HT`) first, then `HD`, `DT`, `NC`,
+    /// `HR`, `SD`, `PF`, `FL` and the remaining mods, so Hidden, Double Time
+    /// and Hard Rock print as `HDDTHR`. Nightcore and Perfect are written on
+    /// their own rather than alongside the Double Time and Sudden Death bits
+    /// osu! sets with them.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 == 0 {
+            return f.write_str("NM");
+        }
+
+        for acronym in self.acronyms(&MOD_DISPLAY_ACRONYMS) {
+            f.write_str(acronym)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Mod {
+    type Err = ReplayError;
+
+    /// Parses concatenated mod acronyms, such as `HDDT`, in any case and order.
+    ///
+    /// `NM` and the empty string parse as [`Mod::NO_MOD`]. As with
+    /// [`Mod::from_api_v2`], Nightcore and Perfect also set Double Time and
+    /// Sudden Death.
+    ///
+    /// # Returns
+    ///
+    /// The combined mods, or `ReplayError::Parse` for an unknown acronym
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("NM") {
+            return Ok(Mod::NO_MOD);
+        }
+        if !s.is_ascii() {
+            return Err(ReplayError::Parse(format!("Invalid mod string: {}", s)));
+        }
+
+        let mut acronyms = Vec::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            // ScoreV2 is the only acronym longer than two characters
+            let len = if rest.len() >= 3 && rest[..3].eq_ignore_ascii_case("SV2") {
+                3
+            } else {
+                2.min(rest.len())
+            };
+            acronyms.push(&rest[..len]);
+            rest = &rest[len..];
+        }
+
+        Mod::from_api_v2(&acronyms)
+    }
+}
+
+/// Implements the bitwise operators of a flag type on its inner `u32`.
+macro_rules! bit_ops {
+    ($flags:ident) => {
+        impl std::ops::BitOr for $flags {
+            type Output = Self;
+
+            fn bitor(self, rhs: Self) -> Self {
+                Self(self.0 | rhs.0)
+            }
+        }
+
+        impl std::ops::BitAnd for $flags {
+            type Output = Self;
+
+            fn bitand(self, rhs: Self) -> Self {
+                Self(self.0 & rhs.0)
+            }
+        }
+
+        impl std::ops::Not for $flags {
+            type Output = Self;
+
+            fn not(self) -> Self {
+                Self(!self.0)
+            }
+        }
+
+        impl std::ops::BitOrAssign for $flags {
+            fn bitor_assign(&mut self, rhs: Self) {
+                self.0 |= rhs.0;
+            }
+        }
+
+        impl std::ops::BitAndAssign for $flags {
+            fn bitand_assign(&mut self, rhs: Self) {
+                self.0 &= rhs.0;
+            }
+        }
+    };
 }
 
 impl From<u32> for Mod {
@@ -96,9 +563,225 @@ impl From<u32> for Mod {
     }
 }
 
+bit_ops!(Mod);
+
+/// An explicit view of a [`Mod`] bitflag, with one boolean per mod.
+///
+/// osu! folds some mods into others when writing the bitflag: Nightcore also
+/// sets Double Time, and Perfect also sets Sudden Death. Here these are
+/// resolved, so `double_time` and `sudden_death` are only set when the
+/// replay used the plain mod.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectiveMods {
+    pub no_fail: bool,
+    pub easy: bool,
+    pub touch_device: bool,
+    pub hidden: bool,
+    pub hard_rock: bool,
+    pub sudden_death: bool,
+    pub perfect: bool,
+    pub double_time: bool,
+    pub nightcore: bool,
+    pub half_time: bool,
+    pub relax: bool,
+    pub flashlight: bool,
+    pub autoplay: bool,
+    pub spun_out: bool,
+    pub autopilot: bool,
+    pub fade_in: bool,
+    pub random: bool,
+    pub cinema: bool,
+    pub target: bool,
+    pub key_coop: bool,
+    pub score_v2: bool,
+    pub mirror: bool,
+    /// The osu!mania key count forced by a key mod, or None if no key mod is set
+    pub mania_keys: Option<u8>,
+}
+
+impl From<Mod> for EffectiveMods {
+    fn from(mods: Mod) -> Self {
+        const KEY_MODS: [(Mod, u8); 9] = [
+            (Mod::KEY1, 1),
+            (Mod::KEY2, 2),
+            (Mod::KEY3, 3),
+            (Mod::KEY4, 4),
+            (Mod::KEY5, 5),
+            (Mod::KEY6, 6),
+            (Mod::KEY7, 7),
+            (Mod::KEY8, 8),
+            (Mod::KEY9, 9),
+        ];
+
+        let nightcore = mods.contains(Mod::NIGHTCORE);
+        let perfect = mods.contains(Mod::PERFECT);
+
+        Self {
+            no_fail: mods.contains(Mod::NO_FAIL),
+            easy: mods.contains(Mod::EASY),
+            touch_device: mods.contains(Mod::TOUCH_DEVICE),
+            hidden: mods.contains(Mod::HIDDEN),
+            hard_rock: mods.contains(Mod::HARD_ROCK),
+            sudden_death: mods.contains(Mod::SUDDEN_DEATH) && !perfect,
+            perfect,
+            double_time: mods.contains(Mod::DOUBLE_TIME) && !nightcore,
+            nightcore,
+            half_time: mods.contains(Mod::HALF_TIME),
+            relax: mods.contains(Mod::RELAX),
+            flashlight: mods.contains(Mod::FLASHLIGHT),
+            autoplay: mods.contains(Mod::AUTOPLAY),
+            spun_out: mods.contains(Mod::SPUN_OUT),
+            autopilot: mods.contains(Mod::AUTOPILOT),
+            fade_in: mods.contains(Mod::FADE_IN),
+            random: mods.contains(Mod::RANDOM),
+            cinema: mods.contains(Mod::CINEMA),
+            target: mods.contains(Mod::TARGET),
+            key_coop: mods.contains(Mod::KEY_COOP),
+            score_v2: mods.contains(Mod::SCORE_V2),
+            mirror: mods.contains(Mod::MIRROR),
+            mania_keys: KEY_MODS
+                .iter()
+                .find(|(key_mod, _)| mods.contains(*key_mod))
+                .map(|&(_, keys)| keys),
+        }
+    }
+}
+
+/// How much a single judgement contributes to an accuracy value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct JudgementWeight {
+    /// The points this judgement earns
+    pub numerator: f64,
+    /// The points this judgement could have earned at best
+    pub denominator: f64,
+}
+
+impl JudgementWeight {
+    pub const fn new(numerator: f64, denominator: f64) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+}
+
+/// The weights of each hit count in an accuracy calculation.
+///
+/// Accuracy is the sum of every count times its numerator weight, over the
+/// sum of every count times its denominator weight. The default is the
+/// classic osu!standard weighting; see [`AccuracyWeights::for_mode`] for the
+/// other modes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AccuracyWeights {
+    pub count_300: JudgementWeight,
+    pub count_100: JudgementWeight,
+    pub count_50: JudgementWeight,
+    pub count_geki: JudgementWeight,
+    pub count_katu: JudgementWeight,
+    pub count_miss: JudgementWeight,
+}
+
+impl Default for AccuracyWeights {
+    fn default() -> Self {
+        Self::for_mode(GameMode::Std)
+    }
+}
+
+impl AccuracyWeights {
+    /// Returns the classic weights osu! uses for a mode's accuracy.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The game mode to get the weights of
+    pub fn for_mode(mode: GameMode) -> Self {
+        let none = JudgementWeight::new(0.0, 0.0);
+
+        match mode {
+            GameMode::Std => Self {
+                count_300: JudgementWeight::new(300.0, 300.0),
+                count_100: JudgementWeight::new(100.0, 300.0),
+                count_50: JudgementWeight::new(50.0, 300.0),
+                count_geki: none,
+                count_katu: none,
+                count_miss: JudgementWeight::new(0.0, 300.0),
+            },
+            GameMode::Taiko => Self {
+                count_300: JudgementWeight::new(1.0, 1.0),
+                count_100: JudgementWeight::new(0.5, 1.0),
+                count_50: JudgementWeight::new(0.0, 1.0),
+                count_geki: none,
+                count_katu: none,
+                count_miss: JudgementWeight::new(0.0, 1.0),
+            },
+            GameMode::Catch => Self {
+                count_300: JudgementWeight::new(1.0, 1.0),
+                count_100: JudgementWeight::new(1.0, 1.0),
+                count_50: JudgementWeight::new(1.0, 1.0),
+                count_geki: none,
+                count_katu: JudgementWeight::new(0.0, 1.0),
+                count_miss: JudgementWeight::new(0.0, 1.0),
+            },
+            GameMode::Mania => Self {
+                count_300: JudgementWeight::new(300.0, 300.0),
+                count_100: JudgementWeight::new(100.0, 300.0),
+                count_50: JudgementWeight::new(50.0, 300.0),
+                count_geki: JudgementWeight::new(300.0, 300.0),
+                count_katu: JudgementWeight::new(200.0, 300.0),
+                count_miss: JudgementWeight::new(0.0, 300.0),
+            },
+        }
+    }
+}
+
+/// Implements serde for a key type as an array of key names, such as `["M1", "K1"]`.
+///
+/// Unknown key bits can't be named, so serializing them is an error.
+#[cfg(feature = "human-readable")]
+macro_rules! key_names_serde {
+    ($key:ident, [$(($flag:ident, $name:literal)),* $(,)?]) => {
+        impl $key {
+            const NAMES: &'static [($key, &'static str)] = &[$(($key::$flag, $name)),*];
+        }
+
+        impl Serialize for $key {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let known = Self::NAMES.iter().fold(0, |bits, (flag, _)| bits | flag.0);
+                if self.0 & !known != 0 {
+                    return Err(serde::ser::Error::custom(format!(
+                        "unknown key bits: {:#x}",
+                        self.0 & !known
+                    )));
+                }
+
+                let names: Vec<&str> = Self::NAMES
+                    .iter()
+                    .filter(|(flag, _)| self.0 & flag.0 != 0)
+                    .map(|&(_, name)| name)
+                    .collect();
+                names.serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $key {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let mut bits = 0;
+                for name in Vec::<String>::deserialize(deserializer)? {
+                    let (flag, _) = Self::NAMES
+                        .iter()
+                        .find(|(_, known)| *known == name)
+                        .ok_or_else(|| de::Error::custom(format!("unknown key name: {}", name)))?;
+                    bits |= flag.0;
+                }
+                Ok(Self(bits))
+            }
+        }
+    };
+}
+
 /// Represents keys that can be pressed during osu!standard gameplay.
 /// Includes mouse buttons (M1, M2), keyboard keys (K1, K2), and smoke.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "human-readable"), derive(Serialize, Deserialize))]
 pub struct Key(pub u32);
 
 impl Key {
@@ -119,9 +802,24 @@ impl From<u32> for Key {
     }
 }
 
+bit_ops!(Key);
+
+#[cfg(feature = "human-readable")]
+key_names_serde!(
+    Key,
+    [
+        (M1, "M1"),
+        (M2, "M2"),
+        (K1, "K1"),
+        (K2, "K2"),
+        (SMOKE, "SMOKE")
+    ]
+);
+
 /// Represents keys that can be pressed during osu!taiko gameplay.
 /// Includes different drum hit types for left and right sides.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "human-readable"), derive(Serialize, Deserialize))]
 pub struct KeyTaiko(pub u32);
 
 impl KeyTaiko {
@@ -141,9 +839,28 @@ impl From<u32> for KeyTaiko {
     }
 }
 
+bit_ops!(KeyTaiko);
+
+#[cfg(feature = "human-readable")]
+key_names_serde!(
+    KeyTaiko,
+    [
+        (LEFT_DON, "LEFT_DON"),
+        (LEFT_KAT, "LEFT_KAT"),
+        (RIGHT_DON, "RIGHT_DON"),
+        (RIGHT_KAT, "RIGHT_KAT"),
+    ]
+);
+
 /// Represents keys that can be pressed during osu!mania gameplay.
 /// Supports up to 18 lanes (K1-K18) for different key configurations.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// In co-op (`Mod::KEY_COOP`) replays the lanes of both players are stored in
+/// the same bits: the first player's lanes come first, followed by the second
+/// player's. A 4K co-op replay uses K1-K4 for the left half and K5-K8 for the
+/// right half.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "human-readable"), derive(Serialize, Deserialize))]
 pub struct KeyMania(pub u32);
 
 impl KeyMania {
@@ -177,6 +894,33 @@ impl From<u32> for KeyMania {
     }
 }
 
+bit_ops!(KeyMania);
+
+#[cfg(feature = "human-readable")]
+key_names_serde!(
+    KeyMania,
+    [
+        (K1, "K1"),
+        (K2, "K2"),
+        (K3, "K3"),
+        (K4, "K4"),
+        (K5, "K5"),
+        (K6, "K6"),
+        (K7, "K7"),
+        (K8, "K8"),
+        (K9, "K9"),
+        (K10, "K10"),
+        (K11, "K11"),
+        (K12, "K12"),
+        (K13, "K13"),
+        (K14, "K14"),
+        (K15, "K15"),
+        (K16, "K16"),
+        (K17, "K17"),
+        (K18, "K18"),
+    ]
+);
+
 /// A single event (frame) in a replay, specific to the game mode.
 ///
 /// Each variant contains mode-specific information about what happened
@@ -198,6 +942,36 @@ impl ReplayEvent {
             ReplayEvent::Mania(event) => event.time_delta,
         }
     }
+
+    /// Sets the time since the previous event, in milliseconds.
+    pub fn set_time_delta(&mut self, time_delta: i32) {
+        match self {
+            ReplayEvent::Osu(event) => event.time_delta = time_delta,
+            ReplayEvent::Taiko(event) => event.time_delta = time_delta,
+            ReplayEvent::Catch(event) => event.time_delta = time_delta,
+            ReplayEvent::Mania(event) => event.time_delta = time_delta,
+        }
+    }
+
+    /// Returns the length of this event once written to a replay's frame block.
+    ///
+    /// This is the length of the `time_delta|x|y|keys,` string the default
+    /// [`Packer`](crate::Packer) writes for this event, trailing comma included.
+    pub fn encoded_byte_len(&self) -> usize {
+        crate::packer::Packer::new().format_frame(self).len() + 1
+    }
+
+    /// Returns the raw key bits of this event, for the mode's key type.
+    ///
+    /// For osu!catch, the dash state is reported as bit 0.
+    pub fn key_bits(&self) -> u32 {
+        match self {
+            ReplayEvent::Osu(event) => event.keys.value(),
+            ReplayEvent::Taiko(event) => event.keys.value(),
+            ReplayEvent::Catch(event) => event.dashing as u32,
+            ReplayEvent::Mania(event) => event.keys.value(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -237,3 +1011,60 @@ pub struct LifeBarState {
     pub time: i32,
     pub life: f32,
 }
+
+/// The letter grade of a play, as shown on the osu! results screen.
+///
+/// The silver grades (`SSH` and `SH`) are the `SS` and `S` grades achieved
+/// with Hidden or Flashlight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Grade {
+    SSH,
+    SS,
+    SH,
+    S,
+    A,
+    B,
+    C,
+    D,
+}
+
+/// The hit counts of a replay under their osu! API v2 judgement names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Statistics {
+    /// 300s (`count_300`)
+    pub great: u16,
+    /// 100s (`count_100`)
+    pub ok: u16,
+    /// 50s (`count_50`)
+    pub meh: u16,
+    /// Misses (`count_miss`)
+    pub miss: u16,
+    /// Gekis (`count_geki`), the MAX judgements in osu!mania
+    pub perfect: u16,
+    /// Katus (`count_katu`), the 200s in osu!mania
+    pub good: u16,
+}
+
+/// The metadata of a replay read by [`Replay::quick_info`](crate::Replay::quick_info).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuickInfo {
+    /// The game mode this replay was played on
+    pub mode: GameMode,
+    /// The user that played this replay
+    pub username: String,
+    /// The hash of the beatmap this replay was played on
+    pub beatmap_hash: String,
+    /// The score of this replay
+    pub score: u32,
+    /// The maximum combo attained in this replay
+    pub max_combo: u16,
+    /// The mods this replay was played with
+    pub mods: Mod,
+}
+
+/// Per-player osu!mania settings carried in the lazer extra-data block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManiaSettings {
+    /// The scroll speed the player used
+    pub scroll_speed: f64,
+}
@@ -1,17 +1,279 @@
-use crate::{error::ReplayError, replay::Replay, types::*};
+use crate::{error::ReplayError, packer::LzmaFormat, replay::Replay, types::*};
 use byteorder::{LittleEndian, ReadBytesExt};
 use chrono::{DateTime, TimeZone, Utc};
-use liblzma::read;
-use std::io::Read;
+use liblzma::{read, stream::Stream};
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::RangeInclusive;
+
+/// Options controlling how strictly a replay is parsed.
+///
+/// The defaults parse real-world replays on a best-effort basis while
+/// capping the decompressed size of the frame block.
+///
+/// # Example
+///
+/// ```rust
+/// use rosu_replay::ParserConfig;
+///
+/// let config = ParserConfig::new()
+///     .with_strict_frames(true)
+///     .with_max_decompressed_size(16 * 1024 * 1024);
+/// assert!(config.strict_frames);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserConfig {
+    /// Whether malformed frames and non-finite coordinates are an error instead of
+    /// being skipped and replaced with 0
+    pub strict_frames: bool,
+    /// Whether replays outside [`SUPPORTED_GAME_VERSIONS`] are an error
+    pub strict_version: bool,
+    /// Whether malformed life bar states are skipped instead of an error
+    pub lenient_life_bar: bool,
+    /// The maximum number of bytes the frame block may decompress to
+    pub max_decompressed_size: u64,
+    /// The LZMA format of the frame block, or `None` to detect it
+    pub lzma_format: Option<LzmaFormat>,
+    /// Whether unknown mode bytes are read as `GameMode::Std` instead of an error
+    pub lenient_mode: bool,
+    /// Whether Nightcore and Perfect also set the Double Time and Sudden Death
+    /// bits in the parsed mods, see [`Mod::normalized`]
+    pub normalize_mods_on_parse: bool,
+    /// Whether lazer data that can't be decoded is an error instead of being
+    /// ignored
+    pub strict_lazer_data: bool,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            strict_frames: false,
+            strict_version: false,
+            lenient_life_bar: false,
+            max_decompressed_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+            lzma_format: None,
+            lenient_mode: false,
+            normalize_mods_on_parse: false,
+            strict_lazer_data: false,
+        }
+    }
+}
+
+impl ParserConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_strict_frames(mut self, strict: bool) -> Self {
+        self.strict_frames = strict;
+        self
+    }
+
+    pub fn with_strict_version(mut self, strict: bool) -> Self {
+        self.strict_version = strict;
+        self
+    }
+
+    pub fn with_lenient_life_bar(mut self, lenient: bool) -> Self {
+        self.lenient_life_bar = lenient;
+        self
+    }
+
+    pub fn with_max_decompressed_size(mut self, max_size: u64) -> Self {
+        self.max_decompressed_size = max_size;
+        self
+    }
+
+    pub fn with_lzma_format(mut self, lzma_format: Option<LzmaFormat>) -> Self {
+        self.lzma_format = lzma_format;
+        self
+    }
+
+    pub fn with_lenient_mode(mut self, lenient: bool) -> Self {
+        self.lenient_mode = lenient;
+        self
+    }
+
+    pub fn with_normalize_mods_on_parse(mut self, normalize: bool) -> Self {
+        self.normalize_mods_on_parse = normalize;
+        self
+    }
+
+    pub fn with_strict_lazer_data(mut self, strict: bool) -> Self {
+        self.strict_lazer_data = strict;
+        self
+    }
+}
 
 /// Helper struct for unpacking .osr format data
 pub struct Unpacker<R: Read> {
     reader: R,
+    config: ParserConfig,
+    skipped_life_bar_states: usize,
+}
+
+/// A stage of parsing a replay, reported to progress callbacks.
+///
+/// Stages are reported in the order they are declared, with `ParsingFrames`
+/// reported repeatedly as frames are parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStage {
+    /// Reading the metadata before the life bar
+    Header,
+    /// Reading the life bar
+    LifeBar,
+    /// Decompressing the frame block
+    Decompressing,
+    /// Parsing frames, with `done` of the `total` frames parsed so far
+    ParsingFrames { done: usize, total: usize },
+    /// The replay has been fully read
+    Done,
+}
+
+/// The recoverable problems found while parsing a replay.
+///
+/// Returned alongside the replay by [`Unpacker::unpack_with_report`] and
+/// [`Replay::from_reader_with_report`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseReport {
+    /// The number of malformed life bar states skipped, see
+    /// [`ParserConfig::lenient_life_bar`]
+    pub skipped_life_bar_states: usize,
 }
 
+/// How many frames are parsed between `ParseStage::ParsingFrames` reports.
+const FRAME_PROGRESS_INTERVAL: usize = 1000;
+
+/// The game versions the unpacker knows how to read.
+///
+/// osu! stable writes its build date as `YYYYMMDD`, while lazer writes
+/// versions starting at `30000000`.
+pub const SUPPORTED_GAME_VERSIONS: RangeInclusive<u32> = 20070101..=39999999;
+
+/// The first game version written by osu!lazer.
+///
+/// Only replays from this version on are read with a lazer data block.
+pub const LAZER_GAME_VERSION: u32 = 30000000;
+
+/// The default cap on the decompressed size of the frame block (256 MiB).
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: u64 = 256 * 1024 * 1024;
+
 impl<R: Read> Unpacker<R> {
     pub fn new(reader: R) -> Self {
-        Self { reader }
+        Self::with_config(reader, ParserConfig::default())
+    }
+
+    /// Creates an unpacker reading from `reader` with the given parser options.
+    pub fn with_config(reader: R, config: ParserConfig) -> Self {
+        Self {
+            reader,
+            config,
+            skipped_life_bar_states: 0,
+        }
+    }
+
+    /// Returns the parser options this unpacker was configured with.
+    pub fn config(&self) -> &ParserConfig {
+        &self.config
+    }
+
+    /// Sets whether replays outside [`SUPPORTED_GAME_VERSIONS`] are rejected.
+    ///
+    /// In strict mode such replays produce a `ReplayError::UnsupportedVersion`
+    /// instead of being parsed on a best-effort basis.
+    pub fn with_strict_version(mut self, strict: bool) -> Self {
+        self.config.strict_version = strict;
+        self
+    }
+
+    /// Sets whether malformed life bar states are skipped instead of failing the parse.
+    ///
+    /// By default, a life bar state that isn't a valid `time|life` pair is an
+    /// error. In lenient mode such states are dropped and counted, see
+    /// [`Unpacker::skipped_life_bar_states`].
+    pub fn with_lenient_life_bar(mut self, lenient: bool) -> Self {
+        self.config.lenient_life_bar = lenient;
+        self
+    }
+
+    /// Returns how many malformed life bar states were skipped in lenient mode.
+    pub fn skipped_life_bar_states(&self) -> usize {
+        self.skipped_life_bar_states
+    }
+
+    /// Sets the maximum number of bytes the frame block may decompress to.
+    ///
+    /// Decompression aborts with `ReplayError::InvalidFormat` once the output
+    /// exceeds this limit, so a small crafted input can't exhaust memory.
+    pub fn with_max_decompressed_size(mut self, max_size: u64) -> Self {
+        self.config.max_decompressed_size = max_size;
+        self
+    }
+
+    /// Forces the LZMA format of the frame block instead of detecting it.
+    ///
+    /// By default (`None`), both the LZMA alone format written by osu! and
+    /// the `.xz` container are accepted. Forcing a format rejects data in the
+    /// other one.
+    pub fn with_lzma_format(mut self, lzma_format: Option<LzmaFormat>) -> Self {
+        self.config.lzma_format = lzma_format;
+        self
+    }
+
+    /// Sets whether malformed frames are rejected instead of skipped.
+    ///
+    /// By default, frames that don't have exactly 4 `|`-separated parts are
+    /// silently dropped and non-finite coordinates are replaced with 0. In strict
+    /// mode they produce a `ReplayError::InvalidFormat` and a `ReplayError::Parse`.
+    pub fn with_strict_frames(mut self, strict: bool) -> Self {
+        self.config.strict_frames = strict;
+        self
+    }
+
+    /// Sets whether the implied mod bits are set on the parsed mods.
+    ///
+    /// Off by default, keeping the mods exactly as stored in the file. When
+    /// enabled, Nightcore also sets Double Time and Perfect also sets Sudden
+    /// Death, so replays from different client versions compare consistently.
+    pub fn with_normalize_mods_on_parse(mut self, normalize: bool) -> Self {
+        self.config.normalize_mods_on_parse = normalize;
+        self
+    }
+
+    /// Sets whether unknown mode bytes are accepted.
+    ///
+    /// By default, a mode byte outside 0-3 is a `ReplayError::InvalidFormat`.
+    /// In lenient mode it is read as `GameMode::Std`, with the original byte
+    /// kept in [`Replay::mode_byte`].
+    pub fn with_lenient_mode(mut self, lenient: bool) -> Self {
+        self.config.lenient_mode = lenient;
+        self
+    }
+
+    /// Sets whether lazer data that can't be decoded is rejected.
+    ///
+    /// By default, bytes after the replay id of a lazer replay that aren't a
+    /// valid lazer data block are ignored and the replay is read without it.
+    /// In strict mode they produce an error instead.
+    pub fn with_strict_lazer_data(mut self, strict: bool) -> Self {
+        self.config.strict_lazer_data = strict;
+        self
+    }
+
+    fn unpack_mode(&mut self, mode_byte: u8) -> Result<GameMode, ReplayError> {
+        if self.config.lenient_mode {
+            Ok(GameMode::from(mode_byte))
+        } else {
+            GameMode::try_from_u8(mode_byte)
+        }
+    }
+
+    fn unpack_mods(&mut self) -> Result<Mod, ReplayError> {
+        let mods = Mod::from(self.unpack_int()?);
+        Ok(if self.config.normalize_mods_on_parse {
+            mods.normalized()
+        } else {
+            mods
+        })
     }
 
     pub fn unpack_byte(&mut self) -> Result<u8, ReplayError> {
@@ -87,6 +349,14 @@ impl<R: Read> Unpacker<R> {
     pub fn unpack_play_data(
         &mut self,
         mode: GameMode,
+    ) -> Result<(Vec<ReplayEvent>, Option<i32>), ReplayError> {
+        self.unpack_play_data_with_progress(mode, &mut |_| {})
+    }
+
+    fn unpack_play_data_with_progress(
+        &mut self,
+        mode: GameMode,
+        progress: &mut dyn FnMut(ParseStage),
     ) -> Result<(Vec<ReplayEvent>, Option<i32>), ReplayError> {
         let replay_length = self.unpack_int()? as usize;
         let mut compressed_data = vec![0u8; replay_length];
@@ -94,15 +364,62 @@ impl<R: Read> Unpacker<R> {
 
         let mut buffer = Vec::new();
 
-        read::XzDecoder::new_multi_decoder(compressed_data.as_slice()).read_to_end(&mut buffer)?;
+        let decoder = match self.config.lzma_format {
+            None => read::XzDecoder::new_multi_decoder(compressed_data.as_slice()),
+            Some(LzmaFormat::Alone) => read::XzDecoder::new_stream(
+                compressed_data.as_slice(),
+                Stream::new_lzma_decoder(u64::MAX)?,
+            ),
+            Some(LzmaFormat::Xz) => read::XzDecoder::new_stream(
+                compressed_data.as_slice(),
+                Stream::new_stream_decoder(u64::MAX, 0)?,
+            ),
+        };
+
+        decoder
+            .take(self.config.max_decompressed_size.saturating_add(1))
+            .read_to_end(&mut buffer)?;
+
+        if buffer.len() as u64 > self.config.max_decompressed_size {
+            return Err(ReplayError::InvalidFormat(format!(
+                "Replay data decompresses to more than {} bytes",
+                self.config.max_decompressed_size
+            )));
+        }
 
         let data_str = String::from_utf8(buffer)?;
-        Self::parse_replay_data(&data_str, mode)
+        Self::parse_frames(&data_str, mode, self.config.strict_frames, progress)
     }
 
     pub fn parse_replay_data(
         replay_data_str: &str,
         mode: GameMode,
+    ) -> Result<(Vec<ReplayEvent>, Option<i32>), ReplayError> {
+        Self::parse_replay_data_with(replay_data_str, mode, false)
+    }
+
+    /// Parses the frame block of a replay, optionally rejecting malformed frames.
+    ///
+    /// # Arguments
+    ///
+    /// * `replay_data_str` - The decompressed frame block
+    /// * `mode` - What mode to parse the replay data as
+    /// * `strict` - Whether a non-empty frame without exactly 4 parts, or with a
+    ///   non-finite coordinate, is an error. Otherwise such frames are skipped
+    ///   and such coordinates are replaced with 0
+    pub fn parse_replay_data_with(
+        replay_data_str: &str,
+        mode: GameMode,
+        strict: bool,
+    ) -> Result<(Vec<ReplayEvent>, Option<i32>), ReplayError> {
+        Self::parse_frames(replay_data_str, mode, strict, &mut |_| {})
+    }
+
+    fn parse_frames(
+        replay_data_str: &str,
+        mode: GameMode,
+        strict: bool,
+        progress: &mut dyn FnMut(ParseStage),
     ) -> Result<(Vec<ReplayEvent>, Option<i32>), ReplayError> {
         // Remove trailing comma if it exists
         let replay_data_str = replay_data_str.trim_end_matches(',');
@@ -116,8 +433,23 @@ impl<R: Read> Unpacker<R> {
         let mut rng_seed = None;
 
         for (i, event_str) in events.iter().enumerate() {
+            if i > 0 && i % FRAME_PROGRESS_INTERVAL == 0 {
+                progress(ParseStage::ParsingFrames {
+                    done: i,
+                    total: events.len(),
+                });
+            }
+
             let parts: Vec<&str> = event_str.split('|').collect();
             if parts.len() != 4 {
+                if strict && !event_str.is_empty() {
+                    return Err(ReplayError::InvalidFormat(format!(
+                        "Frame {} has {} parts, expected 4: {:?}",
+                        i,
+                        parts.len(),
+                        event_str
+                    )));
+                }
                 continue;
             }
 
@@ -153,6 +485,8 @@ impl<R: Read> Unpacker<R> {
                     let y = y_str
                         .parse::<f32>()
                         .map_err(|e| ReplayError::Parse(format!("Invalid y coordinate: {}", e)))?;
+                    let x = Self::finite_coordinate(x, strict)?;
+                    let y = Self::finite_coordinate(y, strict)?;
                     ReplayEvent::Osu(ReplayEventOsu {
                         time_delta,
                         x,
@@ -174,6 +508,7 @@ impl<R: Read> Unpacker<R> {
                     let x = x_str
                         .parse::<f32>()
                         .map_err(|e| ReplayError::Parse(format!("Invalid x coordinate: {}", e)))?;
+                    let x = Self::finite_coordinate(x, strict)?;
                     ReplayEvent::Catch(ReplayEventCatch {
                         time_delta,
                         x,
@@ -194,9 +529,26 @@ impl<R: Read> Unpacker<R> {
             play_data.push(event);
         }
 
+        progress(ParseStage::ParsingFrames {
+            done: events.len(),
+            total: events.len(),
+        });
+
         Ok((play_data, rng_seed))
     }
 
+    /// Rejects a non-finite coordinate in strict mode, or replaces it with 0.
+    fn finite_coordinate(value: f32, strict: bool) -> Result<f32, ReplayError> {
+        match value.is_finite() {
+            true => Ok(value),
+            false if strict => Err(ReplayError::Parse(format!(
+                "Non-finite coordinate: {}",
+                value
+            ))),
+            false => Ok(0.0),
+        }
+    }
+
     pub fn unpack_replay_id(&mut self) -> Result<i64, ReplayError> {
         // Try to read as long first, fallback to int for old replays
         match self.unpack_long() {
@@ -208,43 +560,197 @@ impl<R: Read> Unpacker<R> {
         }
     }
 
+    /// Reads the target practice accuracy that follows the replay id.
+    ///
+    /// osu! writes this little-endian double only for replays played with
+    /// [`Mod::TARGET`], so it must be read before the lazer extra-data block.
+    /// Replays that end right after the replay id have no value.
+    ///
+    /// # Returns
+    ///
+    /// The accuracy, or `None` if the replay ends before it
+    pub fn unpack_target_practice(&mut self) -> Result<Option<f64>, ReplayError> {
+        let mut buffer = Vec::with_capacity(8);
+        self.reader.by_ref().take(8).read_to_end(&mut buffer)?;
+
+        match buffer.len() {
+            0 => Ok(None),
+            8 => Ok(Some(buffer.as_slice().read_f64::<LittleEndian>()?)),
+            _ => Err(ReplayError::UnexpectedEof),
+        }
+    }
+
+    /// Reads the lazer extra-data block that may follow the replay id.
+    ///
+    /// Lazer appends its own score information as an LZMA-compressed JSON
+    /// document, prefixed with its compressed length. Only call this for lazer
+    /// replays: stable replays end after the replay id, but some carry other
+    /// trailing bytes.
+    ///
+    /// # Returns
+    ///
+    /// The decoded JSON document, or `None` if the replay has no extra data or
+    /// it can't be decoded outside of [`ParserConfig::strict_lazer_data`]
+    pub fn unpack_lazer_data(&mut self) -> Result<Option<serde_json::Value>, ReplayError> {
+        let mut remaining = Vec::new();
+        self.reader.read_to_end(&mut remaining)?;
+        if remaining.is_empty() {
+            return Ok(None);
+        }
+
+        match Self::decode_lazer_data(&remaining, self.config.max_decompressed_size) {
+            Ok(data) => Ok(Some(data)),
+            Err(_) if !self.config.strict_lazer_data => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn decode_lazer_data(
+        mut remaining: &[u8],
+        max_decompressed_size: u64,
+    ) -> Result<serde_json::Value, ReplayError> {
+        let length = remaining.read_u32::<LittleEndian>()? as usize;
+        if remaining.len() < length {
+            return Err(ReplayError::UnexpectedEof);
+        }
+
+        let mut buffer = Vec::new();
+        read::XzDecoder::new_multi_decoder(&remaining[..length])
+            .take(max_decompressed_size.saturating_add(1))
+            .read_to_end(&mut buffer)?;
+
+        if buffer.len() as u64 > max_decompressed_size {
+            return Err(ReplayError::InvalidFormat(format!(
+                "Lazer data decompresses to more than {} bytes",
+                max_decompressed_size
+            )));
+        }
+
+        serde_json::from_slice(&buffer)
+            .map_err(|e| ReplayError::Parse(format!("Invalid lazer data: {}", e)))
+    }
+
+    /// Unpacks the life bar string.
+    ///
+    /// A null string gives `None`, while an empty but present string gives
+    /// an empty life bar, so both survive a round-trip.
     pub fn unpack_life_bar(&mut self) -> Result<Option<Vec<LifeBarState>>, ReplayError> {
         let life_bar_string = self.unpack_string()?;
 
         match life_bar_string {
             None => Ok(None),
-            Some(ref s) if s.is_empty() => Ok(None),
+            Some(ref s) if s.is_empty() => Ok(Some(Vec::new())),
             Some(life_bar) => {
                 let life_bar = life_bar.trim_end_matches(',');
-                let states: Result<Vec<LifeBarState>, ReplayError> = life_bar
-                    .split(',')
-                    .map(|state_str| {
-                        let parts: Vec<&str> = state_str.split('|').collect();
-                        if parts.len() != 2 {
-                            return Err(ReplayError::Parse(
-                                "Invalid life bar state format".to_string(),
-                            ));
-                        }
-
-                        let time = parts[0]
-                            .parse::<i32>()
-                            .map_err(|e| ReplayError::Parse(format!("Invalid time: {}", e)))?;
-                        let life = parts[1]
-                            .parse::<f32>()
-                            .map_err(|e| ReplayError::Parse(format!("Invalid life: {}", e)))?;
-
-                        Ok(LifeBarState { time, life })
-                    })
-                    .collect();
+                let mut states = Vec::new();
+
+                for state_str in life_bar.split(',') {
+                    match Self::parse_life_bar_state(state_str) {
+                        Ok(state) => states.push(state),
+                        Err(_) if self.config.lenient_life_bar => self.skipped_life_bar_states += 1,
+                        Err(e) => return Err(e),
+                    }
+                }
 
-                Ok(Some(states?))
+                Ok(Some(states))
             }
         }
     }
 
-    pub fn unpack(mut self) -> Result<Replay, ReplayError> {
-        let mode = GameMode::from(self.unpack_byte()?);
+    fn parse_life_bar_state(state_str: &str) -> Result<LifeBarState, ReplayError> {
+        let parts: Vec<&str> = state_str.split('|').collect();
+        if parts.len() != 2 {
+            return Err(ReplayError::Parse(
+                "Invalid life bar state format".to_string(),
+            ));
+        }
+
+        let time = parts[0]
+            .parse::<i32>()
+            .map_err(|e| ReplayError::Parse(format!("Invalid time: {}", e)))?;
+        let life = parts[1]
+            .parse::<f32>()
+            .map_err(|e| ReplayError::Parse(format!("Invalid life: {}", e)))?;
+
+        Ok(LifeBarState { time, life })
+    }
+
+    /// Reads the replay header up to the mods, without the life bar or frames.
+    pub fn unpack_quick_info(mut self) -> Result<QuickInfo, ReplayError> {
+        let mode_byte = self.unpack_byte()?;
+        let mode = self.unpack_mode(mode_byte)?;
         let game_version = self.unpack_int()?;
+        if self.config.strict_version && !SUPPORTED_GAME_VERSIONS.contains(&game_version) {
+            return Err(ReplayError::UnsupportedVersion(game_version));
+        }
+        let beatmap_hash = self.unpack_string()?.unwrap_or_default();
+        let username = self.unpack_string()?.unwrap_or_default();
+        // Replay hash and the six hit counts
+        self.unpack_string()?;
+        for _ in 0..6 {
+            self.unpack_short()?;
+        }
+        let score = self.unpack_int()?;
+        let max_combo = self.unpack_short()?;
+        self.unpack_byte()?;
+        let mods = self.unpack_mods()?;
+
+        Ok(QuickInfo {
+            mode,
+            username,
+            beatmap_hash,
+            score,
+            max_combo,
+            mods,
+        })
+    }
+
+    pub fn unpack(self) -> Result<Replay, ReplayError> {
+        self.unpack_with_progress(|_| {})
+    }
+
+    /// Reads a replay, reporting each [`ParseStage`] to `progress` as it is reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - The callback invoked at each stage
+    ///
+    /// # Returns
+    ///
+    /// The parsed replay object
+    pub fn unpack_with_progress<F: FnMut(ParseStage)>(
+        mut self,
+        progress: F,
+    ) -> Result<Replay, ReplayError> {
+        self.unpack_with(progress, Self::unpack_replay_id)
+    }
+
+    /// Reads a replay along with the recoverable problems found while parsing it.
+    ///
+    /// # Returns
+    ///
+    /// The parsed replay object and the [`ParseReport`] of its parse
+    pub fn unpack_with_report(mut self) -> Result<(Replay, ParseReport), ReplayError> {
+        let replay = self.unpack_with(|_| {}, Self::unpack_replay_id)?;
+        let report = ParseReport {
+            skipped_life_bar_states: self.skipped_life_bar_states,
+        };
+
+        Ok((replay, report))
+    }
+
+    fn unpack_with<F: FnMut(ParseStage)>(
+        &mut self,
+        mut progress: F,
+        unpack_replay_id: fn(&mut Self) -> Result<i64, ReplayError>,
+    ) -> Result<Replay, ReplayError> {
+        progress(ParseStage::Header);
+        let mode_byte = self.unpack_byte()?;
+        let mode = self.unpack_mode(mode_byte)?;
+        let game_version = self.unpack_int()?;
+        if self.config.strict_version && !SUPPORTED_GAME_VERSIONS.contains(&game_version) {
+            return Err(ReplayError::UnsupportedVersion(game_version));
+        }
         let beatmap_hash = self.unpack_string()?.unwrap_or_default();
         let username = self.unpack_string()?.unwrap_or_default();
         let replay_hash = self.unpack_string()?.unwrap_or_default();
@@ -257,11 +763,36 @@ impl<R: Read> Unpacker<R> {
         let score = self.unpack_int()?;
         let max_combo = self.unpack_short()?;
         let perfect = self.unpack_byte()? != 0;
-        let mods = Mod::from(self.unpack_int()?);
+        let mods = self.unpack_mods()?;
+        progress(ParseStage::LifeBar);
         let life_bar_graph = self.unpack_life_bar()?;
         let timestamp = self.unpack_timestamp()?;
-        let (replay_data, rng_seed) = self.unpack_play_data(mode)?;
-        let replay_id = self.unpack_replay_id()?;
+        progress(ParseStage::Decompressing);
+        let (replay_data, rng_seed) = self.unpack_play_data_with_progress(mode, &mut progress)?;
+        let replay_id = unpack_replay_id(self)?;
+        let target_practice = if mods.contains(Mod::TARGET) {
+            self.unpack_target_practice()?
+        } else {
+            None
+        };
+        let lazer_data = if game_version >= LAZER_GAME_VERSION {
+            self.unpack_lazer_data()?
+        } else {
+            None
+        };
+        let mania_settings = match lazer_data
+            .as_ref()
+            .and_then(|data| data.get("mania_settings"))
+        {
+            Some(settings) => match serde_json::from_value(settings.clone()) {
+                Ok(settings) => Some(settings),
+                Err(_) if !self.config.strict_lazer_data => None,
+                Err(e) => return Err(ReplayError::Parse(format!("Invalid mania settings: {}", e))),
+            },
+            None => None,
+        };
+
+        progress(ParseStage::Done);
 
         Ok(Replay {
             mode,
@@ -284,6 +815,39 @@ impl<R: Read> Unpacker<R> {
             replay_data,
             replay_id,
             rng_seed,
+            target_practice,
+            mania_settings,
+            lazer_data,
+            mode_byte: Some(mode_byte),
         })
     }
 }
+
+impl<R: Read + Seek> Unpacker<R> {
+    /// Reads the replay id, choosing its width from the bytes left in the stream.
+    ///
+    /// Replay ids are 8 bytes wide, but very old replays store them in 4.
+    /// Seeking to the end tells them apart: with fewer than 8 bytes left the
+    /// id is read as 4 bytes. Replays with data after a 4-byte id are still
+    /// read as having an 8-byte id.
+    pub fn unpack_replay_id_seek(&mut self) -> Result<i64, ReplayError> {
+        let position = self.reader.stream_position()?;
+        let end = self.reader.seek(SeekFrom::End(0))?;
+        self.reader.seek(SeekFrom::Start(position))?;
+
+        match end.saturating_sub(position) {
+            0..=3 => Err(ReplayError::UnexpectedEof),
+            4..=7 => Ok(self.unpack_int()? as i64),
+            _ => self.unpack_long(),
+        }
+    }
+
+    /// Reads a replay from a seekable reader.
+    ///
+    /// This is the same as [`Unpacker::unpack`], except that the width of the
+    /// replay id is chosen with [`Unpacker::unpack_replay_id_seek`] instead of
+    /// on a best-effort basis.
+    pub fn unpack_seekable(mut self) -> Result<Replay, ReplayError> {
+        self.unpack_with(|_| {}, Self::unpack_replay_id_seek)
+    }
+}
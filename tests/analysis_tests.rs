@@ -0,0 +1,651 @@
+use rosu_replay::{
+    GameMode, Key, KeyMania, KeyTaiko, ManiaLaneEvent, Mod, Replay, ReplayEvent, ReplayEventCatch,
+    ReplayEventMania, ReplayEventOsu, ReplayEventTaiko,
+};
+
+/// Test frame delta statistics over a known distribution
+#[test]
+fn test_frame_delta_stats() {
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(-1, 256.0, -500.0, 0),
+            osu_event(16, 0.0, 0.0, 0),
+            osu_event(16, 0.0, 0.0, 0),
+            osu_event(17, 0.0, 0.0, 0),
+            osu_event(16, 0.0, 0.0, 0),
+            osu_event(35, 0.0, 0.0, 0),
+        ],
+    );
+
+    let stats = replay.frame_delta_stats().expect("replay has frames");
+    assert_eq!(stats.min, 16);
+    assert_eq!(stats.max, 35);
+    assert_eq!(stats.mean, 20.0);
+    assert_eq!(stats.median, 16.0);
+    assert_eq!(stats.mode_ms, 16);
+
+    let empty = replay_with_events(GameMode::Std, Vec::new());
+    assert!(empty.frame_delta_stats().is_none());
+}
+
+/// Test converting frames into an ndarray
+#[cfg(feature = "ndarray")]
+#[test]
+fn test_to_ndarray() {
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(16, 100.0, 200.0, 5),
+            osu_event(16, 110.0, 210.0, 0),
+            osu_event(17, 120.0, 220.0, 1),
+        ],
+    );
+
+    let array = replay.to_ndarray();
+    assert_eq!(array.shape(), &[3, 4]);
+    assert_eq!(array.row(0).to_vec(), vec![16.0, 100.0, 200.0, 5.0]);
+    assert_eq!(array[[2, 0]], 49.0);
+}
+
+/// Test counting distinct presses per key bit
+#[test]
+fn test_press_count_per_key() {
+    let k1 = Key::K1.value();
+    let m2 = Key::M2.value();
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(16, 0.0, 0.0, k1),
+            osu_event(16, 0.0, 0.0, k1),
+            osu_event(16, 0.0, 0.0, 0),
+            osu_event(16, 0.0, 0.0, k1 | m2),
+            osu_event(16, 0.0, 0.0, m2),
+        ],
+    );
+
+    let counts = replay.press_count_per_key();
+    assert_eq!(counts.get(&k1), Some(&2));
+    assert_eq!(counts.get(&m2), Some(&1));
+    assert_eq!(counts.get(&Key::M1.value()), None);
+}
+
+/// Test grouping frames by the beatmap objects they lead into
+#[test]
+fn test_frames_grouped_by_object() {
+    // Absolute times: 10, 20, 30, 40, 50
+    let replay = replay_with_events(
+        GameMode::Std,
+        (0..5).map(|_| osu_event(10, 0.0, 0.0, 0)).collect(),
+    );
+
+    let groups = replay.frames_grouped_by_object(&[20, 40]);
+    let sizes: Vec<usize> = groups.iter().map(Vec::len).collect();
+
+    assert_eq!(sizes, vec![1, 2, 2]);
+    assert!(std::ptr::eq(groups[1][0], &replay.replay_data[1]));
+}
+
+/// Test resampling to a fixed tick rate interpolates positions but steps keys
+#[test]
+fn test_resample_inputs() {
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![osu_event(0, 0.0, 0.0, 0), osu_event(10, 100.0, 50.0, 1)],
+    );
+
+    let frames = replay.resample_inputs(1000);
+    assert_eq!(frames.len(), 11);
+
+    assert_eq!(frames[5].time, 5);
+    assert_eq!(frames[5].x, 50.0);
+    assert_eq!(frames[5].y, 25.0);
+    assert_eq!(frames[5].keys, 0);
+    assert_eq!(frames[9].keys, 0);
+    assert_eq!(frames[10].keys, 1);
+    assert_eq!(frames[10].x, 100.0);
+
+    assert!(replay.resample_inputs(0).is_empty());
+}
+
+/// Test sampling key-press density in a window
+#[test]
+fn test_input_density_at() {
+    // A press every 100ms, released on the following frame
+    let mut events = Vec::new();
+    for _ in 0..10 {
+        events.push(osu_event(50, 0.0, 0.0, 1));
+        events.push(osu_event(50, 0.0, 0.0, 0));
+    }
+    let replay = replay_with_events(GameMode::Std, events);
+
+    // Presses at 50, 150, ..., 950; the window [300, 700] holds 4 of them
+    assert_eq!(replay.input_density_at(500, 400), 10.0);
+    assert_eq!(replay.input_density_at(5000, 400), 0.0);
+    assert_eq!(replay.input_density_at(500, 0), 0.0);
+}
+
+/// Test telling mouse presses apart from keyboard presses
+#[test]
+fn test_input_device_breakdown() {
+    let m1 = Key::M1.value();
+    let k1 = Key::K1.value() | m1; // osu! sets M1 alongside K1
+    let k2 = Key::K2.value() | Key::M2.value();
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(16, 0.0, 0.0, m1),
+            osu_event(16, 0.0, 0.0, 0),
+            osu_event(16, 0.0, 0.0, k1),
+            osu_event(16, 0.0, 0.0, k1 | k2),
+            osu_event(16, 0.0, 0.0, 0),
+        ],
+    );
+
+    assert_eq!(replay.input_device_breakdown(), (1, 2));
+}
+
+/// Test reversing the frames of a replay
+#[test]
+fn test_reversed_frames() {
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(10, 1.0, 1.0, 0),
+            osu_event(20, 2.0, 2.0, 1),
+            osu_event(30, 3.0, 3.0, 0),
+        ],
+    );
+
+    let reversed = replay.reversed_frames();
+    let deltas: Vec<i32> = reversed.iter().map(|e| e.time_delta()).collect();
+
+    assert_eq!(deltas, vec![10, 30, 20]);
+    assert_eq!(deltas.iter().sum::<i32>(), 60);
+    assert_eq!(reversed[0], osu_event(10, 3.0, 3.0, 0));
+    assert_eq!(reversed[2], osu_event(20, 1.0, 1.0, 0));
+}
+
+/// Test comparing the inputs of two replays
+#[test]
+fn test_input_similarity() {
+    let events: Vec<ReplayEvent> = (0..100)
+        .map(|i| osu_event(16, (i * 5) as f32, 100.0, (i / 10 % 2) as u32))
+        .collect();
+    let replay = replay_with_events(GameMode::Std, events.clone());
+
+    assert_eq!(replay.input_similarity(&replay), 1.0);
+
+    // The same inputs delayed by 80ms only partially line up
+    let mut shifted_events = events;
+    shifted_events[0] = osu_event(96, 0.0, 100.0, 0);
+    let shifted = replay_with_events(GameMode::Std, shifted_events);
+    let similarity = replay.input_similarity(&shifted);
+    assert!(similarity > 0.0 && similarity < 0.5, "{}", similarity);
+
+    let other_mode = replay_with_events(GameMode::Taiko, Vec::new());
+    assert_eq!(replay.input_similarity(&other_mode), 0.0);
+}
+
+/// Test telling osu!mania holds apart from taps
+#[test]
+fn test_mania_hold_notes() {
+    let replay = replay_with_events(
+        GameMode::Mania,
+        vec![
+            // A 20ms tap on lane 0
+            mania_event(100, 0b0001),
+            mania_event(20, 0b0000),
+            // A 300ms hold on lane 2, with a bit beyond 4K that is ignored
+            mania_event(100, 0b0100),
+            mania_event(150, 0b1_0000_0100),
+            mania_event(150, 0b0000),
+        ],
+    );
+
+    assert_eq!(replay.mania_lane_events(4).len(), 2);
+    assert_eq!(
+        replay.mania_hold_notes(4, 100),
+        vec![ManiaLaneEvent {
+            lane: 2,
+            start: 220,
+            end: 520,
+        }]
+    );
+}
+
+/// Test scoring the plausibility of frame timing
+#[test]
+fn test_timing_integrity() {
+    // Jittery deltas with a press every 12 frames
+    let jitter = [16, 17, 16, 17, 15, 18];
+    let clean = replay_with_events(
+        GameMode::Std,
+        (0..240)
+            .map(|i| osu_event(jitter[i % jitter.len()], 0.0, 0.0, (i % 12 == 0) as u32))
+            .collect(),
+    );
+    assert_eq!(clean.timing_integrity(), 1.0);
+
+    // Identical deltas, frames going back in time and a key press every frame
+    let k1 = Key::K1.value();
+    let k2 = Key::K2.value();
+    let mut events: Vec<ReplayEvent> = (0..240)
+        .map(|i| osu_event(16, 0.0, 0.0, if i % 2 == 0 { k1 } else { k2 }))
+        .collect();
+    for i in [50, 100, 150, 200] {
+        events[i] = osu_event(-16, 0.0, 0.0, k1);
+    }
+    let tampered = replay_with_events(GameMode::Std, events);
+    assert!(
+        tampered.timing_integrity() < 0.3,
+        "{}",
+        tampered.timing_integrity()
+    );
+
+    let empty = replay_with_events(GameMode::Std, Vec::new());
+    assert_eq!(empty.timing_integrity(), 1.0);
+}
+
+/// Test hashing frames independently of metadata
+#[test]
+fn test_frames_content_hash() {
+    let events: Vec<ReplayEvent> = (0..10).map(|i| osu_event(16, i as f32, 100.0, 0)).collect();
+    let replay = replay_with_events(GameMode::Std, events.clone());
+
+    let mut other = replay_with_events(GameMode::Std, events);
+    other.username = "SomeoneElse".to_string();
+    other.score = 1;
+    assert_eq!(replay.frames_content_hash(), other.frames_content_hash());
+
+    other.replay_data[5] = osu_event(16, 5.0, 101.0, 0);
+    assert_ne!(replay.frames_content_hash(), other.frames_content_hash());
+}
+
+/// Test detecting pauses from large gaps between frames
+#[test]
+fn test_detect_pauses() {
+    let mut events: Vec<ReplayEvent> = (0..20).map(|_| osu_event(16, 0.0, 0.0, 0)).collect();
+    events[5] = osu_event(500, 0.0, 0.0, 0);
+    events[10] = osu_event(5000, 0.0, 0.0, 0);
+    let replay = replay_with_events(GameMode::Std, events);
+
+    // Frames 0-4 end at 80, frame 5 is at 580, frames 6-9 end at 644
+    assert_eq!(replay.detect_pauses(1000), vec![(644, 5644)]);
+    assert_eq!(replay.detect_pauses(100).len(), 2);
+    assert!(replay.detect_pauses(10000).is_empty());
+}
+
+/// Test that the intro skip before the first key press isn't a pause
+#[test]
+fn test_detect_pauses_ignores_intro_skip() {
+    let mut events = vec![
+        osu_event(0, 256.0, 192.0, 0),
+        osu_event(-1, 256.0, 192.0, 0),
+        osu_event(4000, 256.0, 192.0, 0),
+        osu_event(16, 256.0, 192.0, 1),
+    ];
+    events.extend((0..10).map(|_| osu_event(16, 256.0, 192.0, 1)));
+    events.push(osu_event(6000, 256.0, 192.0, 0));
+    let replay = replay_with_events(GameMode::Std, events);
+
+    // The first key press is at 4015 and the 10 frames after it end at 4175
+    assert_eq!(replay.gameplay_start_offset_ms(), 4015);
+    assert_eq!(replay.detect_pauses(1000), vec![(4175, 10175)]);
+}
+
+/// Test flagging an osu!taiko combo the inputs can't account for
+#[test]
+fn test_estimate_max_combo_taiko() {
+    // 50 presses, alternating between the left don and the right kat
+    let mut events = Vec::new();
+    for i in 0..50 {
+        let key = if i % 2 == 0 {
+            KeyTaiko::LEFT_DON
+        } else {
+            KeyTaiko::RIGHT_KAT
+        };
+        events.push(taiko_event(100, key.value()));
+        events.push(taiko_event(50, 0));
+    }
+    let mut replay = replay_with_events(GameMode::Taiko, events);
+
+    assert_eq!(replay.estimate_max_combo_taiko(), Some(50));
+
+    replay.max_combo = 45;
+    assert!(!replay.max_combo_exceeds_estimate());
+
+    replay.max_combo = 900;
+    assert!(replay.max_combo_exceeds_estimate());
+
+    replay.mode = GameMode::Std;
+    assert_eq!(replay.estimate_max_combo_taiko(), None);
+    assert!(!replay.max_combo_exceeds_estimate());
+}
+
+/// Test subsampling the cursor path for previews
+#[test]
+fn test_preview_path() {
+    let replay = replay_with_events(
+        GameMode::Std,
+        (0..1000)
+            .map(|i| osu_event(16, i as f32, (i * 2) as f32, 0))
+            .collect(),
+    );
+
+    let path = replay.preview_path(50);
+    assert!(path.len() <= 50);
+    assert_eq!(path.first(), Some(&(0.0, 0.0)));
+    assert_eq!(path.last(), Some(&(999.0, 1998.0)));
+
+    assert_eq!(replay.preview_path(5000).len(), 1000);
+    assert!(replay.preview_path(0).is_empty());
+}
+
+/// Test the cursor bounding box
+#[test]
+fn test_cursor_bounds() {
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(16, 100.0, 200.0, 0),
+            osu_event(16, -20.0, 50.0, 0),
+            osu_event(16, 530.0, 300.0, 1),
+            osu_event(16, 256.0, 400.0, 0),
+        ],
+    );
+    assert_eq!(replay.cursor_bounds(), Some((-20.0, 50.0, 530.0, 400.0)));
+
+    let empty = replay_with_events(GameMode::Std, Vec::new());
+    assert_eq!(empty.cursor_bounds(), None);
+
+    let mania = replay_with_events(GameMode::Mania, vec![mania_event(16, 1)]);
+    assert_eq!(mania.cursor_bounds(), None);
+}
+
+/// Test labeling which objects had a nearby key press
+#[test]
+fn test_keys_during_object() {
+    // Press at 1010ms, release at 1100ms, nothing near 2000ms
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(1000, 0.0, 0.0, 0),
+            osu_event(10, 0.0, 0.0, 1),
+            osu_event(90, 0.0, 0.0, 0),
+            osu_event(1500, 0.0, 0.0, 0),
+        ],
+    );
+
+    assert_eq!(
+        replay.keys_during_object(&[1000, 2000], 50),
+        vec![true, false]
+    );
+    assert_eq!(
+        replay.keys_during_object(&[1000, 2000], 5),
+        vec![false, false]
+    );
+    assert!(replay.keys_during_object(&[], 50).is_empty());
+}
+
+/// Test round-tripping frames through the protobuf encoding
+#[cfg(feature = "prost")]
+#[test]
+fn test_proto_round_trip() {
+    use prost::Message;
+    use rosu_replay::FramesProto;
+
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(-1, 256.0, -500.0, 0),
+            osu_event(16, 100.5, 200.25, 5),
+            osu_event(17, 110.0, 210.0, 0),
+        ],
+    );
+
+    let bytes = replay.to_proto().encode_to_vec();
+    let decoded = FramesProto::decode(bytes.as_slice()).unwrap();
+    assert_eq!(Replay::from_proto(decoded), replay.replay_data);
+
+    let mania = replay_with_events(
+        GameMode::Mania,
+        vec![mania_event(16, 3), mania_event(20, 0)],
+    );
+    let bytes = mania.to_proto().encode_to_vec();
+    let decoded = FramesProto::decode(bytes.as_slice()).unwrap();
+    assert_eq!(Replay::from_proto(decoded), mania.replay_data);
+}
+
+/// Test finding the start of gameplay after the setup frames
+#[test]
+fn test_gameplay_start_offset_ms() {
+    let replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(0, 256.0, -500.0, 0),
+            osu_event(-1, 256.0, -500.0, 0),
+            osu_event(1500, 100.0, 100.0, 0),
+            osu_event(16, 110.0, 110.0, 0),
+            osu_event(17, 120.0, 120.0, 1),
+            osu_event(16, 130.0, 130.0, 0),
+        ],
+    );
+    assert_eq!(replay.gameplay_start_offset_ms(), 1532);
+
+    let idle = replay_with_events(GameMode::Std, vec![osu_event(-1, 0.0, 0.0, 0)]);
+    assert_eq!(idle.gameplay_start_offset_ms(), 0);
+}
+
+/// Test dividing frame times by the Double Time rate
+#[test]
+fn test_events_at_native_rate() {
+    let mut replay = replay_with_events(
+        GameMode::Std,
+        vec![
+            osu_event(300, 0.0, 0.0, 0),
+            osu_event(150, 10.0, 0.0, 1),
+            osu_event(1050, 20.0, 0.0, 0),
+        ],
+    );
+
+    let times: Vec<i64> = replay
+        .events_at_native_rate()
+        .iter()
+        .map(|(time, _)| *time)
+        .collect();
+    assert_eq!(times, vec![300, 450, 1500]);
+
+    replay.mods = Mod::DOUBLE_TIME;
+    let events = replay.events_at_native_rate();
+    let times: Vec<i64> = events.iter().map(|(time, _)| *time).collect();
+    assert_eq!(times, vec![200, 300, 1000]);
+    assert_eq!(events[1].1, replay.replay_data[1]);
+}
+
+/// Test detecting uniformly compressed frame timing
+#[test]
+fn test_detect_timewarp() {
+    let frames = |deltas: &[i32]| -> Vec<ReplayEvent> {
+        deltas
+            .iter()
+            .cycle()
+            .take(300)
+            .map(|&delta| osu_event(delta, 256.0, 192.0, 0))
+            .collect()
+    };
+
+    let normal = replay_with_events(GameMode::Std, frames(&[16, 17, 17]));
+    assert!(!normal.detect_timewarp(16.67, 0.1));
+
+    let warped = replay_with_events(GameMode::Std, frames(&[11, 12, 12]));
+    assert!(warped.detect_timewarp(16.67, 0.1));
+
+    // 60 fps deltas are too short for a Double Time play
+    let mut double_time = replay_with_events(GameMode::Std, frames(&[16, 17, 17]));
+    double_time.mods = Mod::DOUBLE_TIME;
+    assert!(double_time.detect_timewarp(16.67, 0.1));
+
+    let empty = replay_with_events(GameMode::Std, Vec::new());
+    assert!(!empty.detect_timewarp(16.67, 0.1));
+}
+
+/// Test the osu!catch catcher path and traveled distance
+#[test]
+fn test_catch_distance_traveled() {
+    let replay = replay_with_events(
+        GameMode::Catch,
+        vec![
+            catch_event(16, 256.0, false),
+            catch_event(16, 300.0, true),
+            catch_event(16, 200.0, true),
+            catch_event(16, 200.0, false),
+            catch_event(16, 250.5, false),
+        ],
+    );
+
+    assert_eq!(
+        replay.catcher_path(),
+        vec![
+            (16, 256.0),
+            (32, 300.0),
+            (48, 200.0),
+            (64, 200.0),
+            (80, 250.5)
+        ]
+    );
+    assert_eq!(replay.catch_distance_traveled(), 44.0 + 100.0 + 50.5);
+
+    let standard = replay_with_events(GameMode::Std, vec![osu_event(16, 0.0, 0.0, 0)]);
+    assert!(standard.catcher_path().is_empty());
+    assert_eq!(standard.catch_distance_traveled(), 0.0);
+}
+
+/// Test the columnar key and time series
+#[test]
+fn test_key_and_time_series() {
+    let replay = replay_with_events(
+        GameMode::Mania,
+        vec![
+            mania_event(-1, 0),
+            mania_event(20, 5),
+            mania_event(16, 4),
+            mania_event(17, 0),
+        ],
+    );
+
+    let keys = replay.key_series();
+    let times = replay.time_series();
+    assert_eq!(keys.len(), replay.replay_data.len());
+    assert_eq!(times.len(), replay.replay_data.len());
+    assert_eq!(keys, vec![0, 5, 4, 0]);
+    assert_eq!(times, vec![-1, 19, 35, 52]);
+}
+
+/// Test splitting a co-op osu!mania replay into two players
+#[test]
+fn test_split_coop() {
+    let mut replay = replay_with_events(
+        GameMode::Mania,
+        vec![mania_event(16, 0b1001_0011), mania_event(20, 0b0100_0000)],
+    );
+    assert!(replay.split_coop(4).is_none());
+
+    replay.mods = Mod(Mod::KEY4.value() | Mod::KEY_COOP.value());
+    let (first, second) = replay.split_coop(4).unwrap();
+
+    assert_eq!(first, vec![mania_event(16, 0b0011), mania_event(20, 0)]);
+    assert_eq!(
+        second,
+        vec![mania_event(16, 0b1001), mania_event(20, 0b0100)]
+    );
+}
+
+/// Test estimating the client frame rate from the deltas
+#[test]
+fn test_estimated_frame_rate() {
+    let frames = |deltas: &[i32]| -> Vec<ReplayEvent> {
+        deltas
+            .iter()
+            .cycle()
+            .take(300)
+            .map(|&delta| osu_event(delta, 256.0, 192.0, 0))
+            .collect()
+    };
+
+    let mut replay = replay_with_events(GameMode::Std, frames(&[17, 16, 17]));
+    let rate = replay.estimated_frame_rate().unwrap();
+    assert!((rate - 60.0).abs() < 3.0, "rate was {}", rate);
+
+    // 60 fps with Double Time records 25ms of beatmap time per frame
+    replay.replay_data = frames(&[25]);
+    replay.mods = Mod::DOUBLE_TIME;
+    let rate = replay.estimated_frame_rate().unwrap();
+    assert!((rate - 60.0).abs() < 1e-9, "rate was {}", rate);
+
+    let single = replay_with_events(GameMode::Std, frames(&[16])[..1].to_vec());
+    assert_eq!(single.estimated_frame_rate(), None);
+}
+
+// Helper functions for creating test data
+
+fn replay_with_events(mode: GameMode, replay_data: Vec<ReplayEvent>) -> Replay {
+    Replay {
+        mode,
+        game_version: 20240101,
+        beatmap_hash: "abcdef1234567890".to_string(),
+        username: "TestPlayer".to_string(),
+        replay_hash: "fedcba0987654321".to_string(),
+        count_300: 100,
+        count_100: 10,
+        count_50: 5,
+        count_geki: 20,
+        count_katu: 8,
+        count_miss: 2,
+        score: 1000000,
+        max_combo: 150,
+        perfect: false,
+        mods: Mod::NO_MOD,
+        life_bar_graph: None,
+        timestamp: chrono::Utc::now(),
+        replay_data,
+        replay_id: 12345,
+        rng_seed: None,
+        target_practice: None,
+        mania_settings: None,
+        lazer_data: None,
+        mode_byte: None,
+    }
+}
+
+fn osu_event(time_delta: i32, x: f32, y: f32, keys: u32) -> ReplayEvent {
+    ReplayEvent::Osu(ReplayEventOsu {
+        time_delta,
+        x,
+        y,
+        keys: Key(keys),
+    })
+}
+
+fn mania_event(time_delta: i32, keys: u32) -> ReplayEvent {
+    ReplayEvent::Mania(ReplayEventMania {
+        time_delta,
+        keys: KeyMania(keys),
+    })
+}
+
+fn taiko_event(time_delta: i32, keys: u32) -> ReplayEvent {
+    ReplayEvent::Taiko(ReplayEventTaiko {
+        time_delta,
+        x: 0,
+        keys: KeyTaiko(keys),
+    })
+}
+
+fn catch_event(time_delta: i32, x: f32, dashing: bool) -> ReplayEvent {
+    ReplayEvent::Catch(ReplayEventCatch {
+        time_delta,
+        x,
+        dashing,
+    })
+}
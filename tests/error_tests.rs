@@ -1,3 +1,4 @@
+use rosu_replay::unpacker::Unpacker;
 use rosu_replay::{Replay, ReplayError};
 use std::io::Cursor;
 
@@ -141,6 +142,43 @@ fn test_parse_error() {
     }
 }
 
+/// Test that decompression aborts once the frame block exceeds the size limit
+#[test]
+fn test_decompressed_size_limit() -> Result<(), Box<dyn std::error::Error>> {
+    let data = std::fs::read("assets/test.osr")?;
+
+    let result = Unpacker::new(Cursor::new(&data))
+        .with_max_decompressed_size(1024)
+        .unpack();
+    assert!(matches!(result, Err(ReplayError::InvalidFormat(_))));
+
+    // The default limit comfortably fits a normal replay
+    assert!(Unpacker::new(Cursor::new(&data)).unpack().is_ok());
+
+    Ok(())
+}
+
+/// Test that strict version gating rejects absurd game versions
+#[test]
+fn test_unsupported_version_error() -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = std::fs::read("assets/test.osr")?;
+    data[1..5].copy_from_slice(&4_000_000_000u32.to_le_bytes());
+
+    let result = Unpacker::new(Cursor::new(&data))
+        .with_strict_version(true)
+        .unpack();
+    assert!(matches!(
+        result,
+        Err(ReplayError::UnsupportedVersion(4_000_000_000))
+    ));
+
+    // Without strict mode the version is read as-is
+    let replay = Unpacker::new(Cursor::new(&data)).unpack()?;
+    assert_eq!(replay.game_version, 4_000_000_000);
+
+    Ok(())
+}
+
 /// Test error display messages
 #[test]
 fn test_error_display() {
@@ -200,3 +238,16 @@ fn test_concurrent_error_handling() {
         assert!(result.is_err());
     }
 }
+
+/// Test building errors through the convenience constructors
+#[test]
+fn test_error_constructors() {
+    match ReplayError::parse("x") {
+        ReplayError::Parse(message) => assert_eq!(message, "x"),
+        other => panic!("Expected a parse error, got: {:?}", other),
+    }
+
+    let error = ReplayError::invalid_format(format_args!("bad {}", 1));
+    assert!(matches!(&error, ReplayError::InvalidFormat(message) if message == "bad 1"));
+    assert_eq!(error.to_string(), "Invalid replay format: bad 1");
+}
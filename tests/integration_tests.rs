@@ -1,4 +1,10 @@
-use rosu_replay::{GameMode, Key, KeyMania, KeyTaiko, LifeBarState, Mod, Replay, ReplayEvent};
+use rosu_replay::unpacker::Unpacker;
+use rosu_replay::{
+    AccuracyWeights, EffectiveMods, GameMode, Grade, JudgementWeight, Key, KeyMania, KeyTaiko,
+    LifeBarState, LzmaFormat, Mod, Packer, PackerOptions, ParserConfig, Replay, ReplayBuilder,
+    ReplayError, ReplayEvent, ReplayEventMania, ReplayEventOsu, Statistics,
+};
+use std::io::Cursor;
 
 /// Test parsing basic replay data structures
 #[test]
@@ -10,6 +16,79 @@ fn test_game_mode_conversion() {
     assert_eq!(GameMode::from(255), GameMode::Std); // Default fallback
 }
 
+/// Test that game modes serialize as osu-web ruleset short names
+#[test]
+fn test_game_mode_serde() -> Result<(), Box<dyn std::error::Error>> {
+    let json = serde_json::to_string(&GameMode::Catch)?;
+    assert_eq!(json, "\"fruits\"");
+    assert_eq!(serde_json::from_str::<GameMode>(&json)?, GameMode::Catch);
+
+    assert_eq!(serde_json::from_str::<GameMode>("3")?, GameMode::Mania);
+    assert_eq!(
+        serde_json::from_str::<GameMode>("\"Taiko\"")?,
+        GameMode::Taiko
+    );
+    assert!(serde_json::from_str::<GameMode>("4").is_err());
+    assert!(serde_json::from_str::<GameMode>("\"ctb\"").is_err());
+
+    Ok(())
+}
+
+/// Test that game modes round-trip through a non-self-describing format
+#[test]
+fn test_game_mode_bincode() -> Result<(), Box<dyn std::error::Error>> {
+    for mode in [
+        GameMode::Std,
+        GameMode::Taiko,
+        GameMode::Catch,
+        GameMode::Mania,
+    ] {
+        let bytes = bincode::serialize(&mode)?;
+        assert_eq!(bytes, vec![mode as u8]);
+        assert_eq!(bincode::deserialize::<GameMode>(&bytes)?, mode);
+    }
+    assert!(bincode::deserialize::<GameMode>(&[4]).is_err());
+
+    let replay = Replay::from_path("assets/test.osr")?;
+    let bytes = bincode::serialize(&replay)?;
+    let decoded: Replay = bincode::deserialize(&bytes)?;
+    assert_eq!(decoded.mode, replay.mode);
+    assert_eq!(decoded.replay_hash, replay.replay_hash);
+    assert_eq!(decoded.replay_data.len(), replay.replay_data.len());
+
+    Ok(())
+}
+
+/// Test that keys serialize as name arrays with the human-readable feature
+#[cfg(feature = "human-readable")]
+#[test]
+fn test_key_serde_names() -> Result<(), Box<dyn std::error::Error>> {
+    let keys = Key(Key::M1.value() | Key::K1.value());
+    let json = serde_json::to_string(&keys)?;
+    assert_eq!(json, r#"["M1","K1"]"#);
+    assert_eq!(serde_json::from_str::<Key>(&json)?, keys);
+
+    let lanes = KeyMania(KeyMania::K1.value() | KeyMania::K18.value());
+    assert_eq!(serde_json::to_string(&lanes)?, r#"["K1","K18"]"#);
+
+    assert!(serde_json::from_str::<Key>(r#"["K3"]"#).is_err());
+    assert!(serde_json::to_string(&Key(1 << 8)).is_err());
+
+    Ok(())
+}
+
+/// Test that keys serialize as integers by default
+#[cfg(not(feature = "human-readable"))]
+#[test]
+fn test_key_serde_integer() -> Result<(), Box<dyn std::error::Error>> {
+    let keys = Key(Key::M1.value() | Key::K1.value());
+    let json = serde_json::to_string(&keys)?;
+    assert_eq!(json, "5");
+    assert_eq!(serde_json::from_str::<Key>(&json)?, keys);
+
+    Ok(())
+}
+
 #[test]
 fn test_mod_operations() {
     let no_mod = Mod::NO_MOD;
@@ -56,6 +135,106 @@ fn test_mania_keys() {
     assert_eq!(KeyMania::K18.value(), 1 << 17);
 }
 
+/// Test converting mods to and from API v2 acronym arrays
+#[test]
+fn test_mod_api_v2_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let mods = Mod::from_api_v2(&["HD", "NC"])?;
+    assert_eq!(
+        mods,
+        Mod(Mod::HIDDEN.value() | Mod::DOUBLE_TIME.value() | Mod::NIGHTCORE.value())
+    );
+    assert_eq!(mods.to_api_v2(), vec!["HD", "NC"]);
+
+    let acronyms = mods.to_api_v2();
+    let acronyms: Vec<&str> = acronyms.iter().map(String::as_str).collect();
+    assert_eq!(Mod::from_api_v2(&acronyms)?, mods);
+
+    assert_eq!(Mod::from_api_v2(&[])?, Mod::NO_MOD);
+    assert!(Mod::NO_MOD.to_api_v2().is_empty());
+    assert!(matches!(
+        Mod::from_api_v2(&["HD", "XX"]),
+        Err(ReplayError::Parse(_))
+    ));
+
+    Ok(())
+}
+
+/// Test the mod convenience predicates on replays
+#[test]
+fn test_replay_mod_predicates() {
+    let mut replay = create_test_replay();
+    replay.mods = Mod(Mod::HIDDEN.value() | Mod::HARD_ROCK.value() | Mod::DOUBLE_TIME.value());
+
+    type Predicate = fn(&Replay) -> bool;
+    let predicates: [(Predicate, Mod); 14] = [
+        (Replay::has_no_fail, Mod::NO_FAIL),
+        (Replay::has_easy, Mod::EASY),
+        (Replay::has_hidden, Mod::HIDDEN),
+        (Replay::has_hard_rock, Mod::HARD_ROCK),
+        (Replay::has_sudden_death, Mod::SUDDEN_DEATH),
+        (Replay::has_double_time, Mod::DOUBLE_TIME),
+        (Replay::has_relax, Mod::RELAX),
+        (Replay::has_half_time, Mod::HALF_TIME),
+        (Replay::has_nightcore, Mod::NIGHTCORE),
+        (Replay::has_flashlight, Mod::FLASHLIGHT),
+        (Replay::has_spun_out, Mod::SPUN_OUT),
+        (Replay::has_autopilot, Mod::AUTOPILOT),
+        (Replay::has_perfect, Mod::PERFECT),
+        (Replay::has_score_v2, Mod::SCORE_V2),
+    ];
+
+    for (predicate, mod_flag) in predicates {
+        assert_eq!(predicate(&replay), replay.mods.contains(mod_flag));
+    }
+    assert!(replay.has_hidden() && replay.has_hard_rock() && replay.has_double_time());
+    assert!(!replay.has_flashlight());
+}
+
+/// Test expanding the mod bitflag into explicit booleans
+#[test]
+fn test_effective_mods() {
+    let mut replay = create_test_replay();
+    // osu! writes Nightcore together with Double Time
+    replay.mods = Mod(Mod::HIDDEN.value()
+        | Mod::HARD_ROCK.value()
+        | Mod::DOUBLE_TIME.value()
+        | Mod::NIGHTCORE.value());
+
+    assert_eq!(
+        replay.effective_mods(),
+        EffectiveMods {
+            hidden: true,
+            hard_rock: true,
+            nightcore: true,
+            ..EffectiveMods::default()
+        }
+    );
+
+    replay.mods = Mod(Mod::KEY7.value() | Mod::SUDDEN_DEATH.value());
+    let mods = replay.effective_mods();
+    assert_eq!(mods.mania_keys, Some(7));
+    assert!(mods.sudden_death && !mods.perfect);
+}
+
+/// Test the mode-aware judgement totals
+#[test]
+fn test_judged_objects_and_successful_hits() {
+    let mut replay = create_test_replay();
+
+    // 100 + 10 + 5 hits and 2 misses; gekis and katus are subsets
+    assert_eq!(replay.successful_hits(), 115);
+    assert_eq!(replay.judged_objects(), 117);
+
+    // Mania counts MAX (geki) and 200 (katu) as separate judgements
+    replay.mode = GameMode::Mania;
+    assert_eq!(replay.successful_hits(), 143);
+    assert_eq!(replay.judged_objects(), 145);
+    assert_eq!(
+        replay.judged_objects() - replay.successful_hits(),
+        replay.count_miss as u32
+    );
+}
+
 /// Test creating a minimal valid replay
 #[test]
 fn test_create_minimal_replay() {
@@ -102,6 +281,395 @@ fn test_replay_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Test formatting the uncompressed frame block
+#[test]
+fn test_replay_data_string() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = create_test_replay();
+    replay.replay_data = vec![
+        create_osu_event(),
+        ReplayEvent::Osu(rosu_replay::ReplayEventOsu {
+            time_delta: 17,
+            x: 100.5,
+            y: -3.25,
+            keys: Key(Key::K1.value() | Key::M1.value()),
+        }),
+    ];
+
+    assert_eq!(
+        replay.replay_data_string()?,
+        "16|256|192|1,17|100.5|-3.25|5,-12345|0|0|67890,"
+    );
+
+    replay.rng_seed = None;
+    assert_eq!(
+        replay.replay_data_string()?,
+        "16|256|192|1,17|100.5|-3.25|5,"
+    );
+
+    Ok(())
+}
+
+/// Test that the encoded length of each event matches the formatted frame
+#[test]
+fn test_encoded_byte_len() -> Result<(), Box<dyn std::error::Error>> {
+    let events = [
+        create_osu_event(),
+        ReplayEvent::Osu(ReplayEventOsu {
+            time_delta: -3,
+            x: 123.456,
+            y: -0.5,
+            keys: Key(15),
+        }),
+        create_taiko_event(),
+        create_catch_event(),
+        ReplayEvent::Mania(ReplayEventMania {
+            time_delta: 1234,
+            keys: KeyMania(0b1010),
+        }),
+    ];
+
+    let mut replay = create_test_replay();
+    replay.rng_seed = None;
+
+    for event in events {
+        replay.replay_data = vec![event.clone()];
+        assert_eq!(event.encoded_byte_len(), replay.replay_data_string()?.len());
+    }
+
+    Ok(())
+}
+
+/// Test that non-finite coordinates are rejected when packing
+#[test]
+fn test_pack_rejects_non_finite_coordinates() {
+    let mut replay = create_test_replay();
+    if let ReplayEvent::Osu(event) = &mut replay.replay_data[0] {
+        event.y = f32::INFINITY;
+    }
+
+    assert!(matches!(replay.pack(), Err(ReplayError::InvalidFormat(_))));
+}
+
+/// Test the pack-and-reparse integrity check
+#[test]
+fn test_verify_round_trip() {
+    let mut replay = create_test_replay();
+    assert!(replay.verify_round_trip().unwrap());
+
+    if let ReplayEvent::Osu(event) = &mut replay.replay_data[1] {
+        event.x = f32::NAN;
+    }
+    assert!(replay.verify_round_trip().is_err());
+}
+
+/// Test measuring the compressed frame block without a full pack
+#[test]
+fn test_compressed_frame_size() -> Result<(), Box<dyn std::error::Error>> {
+    let replay = create_test_replay();
+    let data = replay.pack()?;
+
+    // Read up to the length prefix of the frame block
+    let mut unpacker = Unpacker::new(Cursor::new(&data));
+    unpacker.unpack_byte()?;
+    unpacker.unpack_int()?;
+    for _ in 0..3 {
+        unpacker.unpack_string()?;
+    }
+    for _ in 0..6 {
+        unpacker.unpack_short()?;
+    }
+    unpacker.unpack_int()?;
+    unpacker.unpack_short()?;
+    unpacker.unpack_byte()?;
+    unpacker.unpack_int()?;
+    unpacker.unpack_life_bar()?;
+    unpacker.unpack_timestamp()?;
+    let frame_block_size = unpacker.unpack_int()? as usize;
+
+    assert_eq!(replay.compressed_frame_size()?, frame_block_size);
+
+    Ok(())
+}
+
+/// Test that writing a replay reports the number of bytes written
+#[test]
+fn test_write_to_returns_byte_count() -> Result<(), Box<dyn std::error::Error>> {
+    let replay = create_test_replay();
+
+    let mut buffer = Vec::new();
+    let written = replay.write_to_counted(&mut buffer)?;
+
+    assert_eq!(written, replay.pack()?.len());
+    assert_eq!(written, buffer.len());
+
+    let mut buffer = Vec::new();
+    replay.write_to(&mut buffer)?;
+    assert_eq!(buffer.len(), written);
+
+    Ok(())
+}
+
+/// Test exporting frames as newline-delimited JSON
+#[test]
+fn test_to_ndjson() -> Result<(), Box<dyn std::error::Error>> {
+    let replay = create_test_replay();
+
+    let mut buffer = Vec::new();
+    replay.to_ndjson(&mut buffer)?;
+    let output = String::from_utf8(buffer)?;
+
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), replay.replay_data.len());
+
+    for (i, line) in lines.iter().enumerate() {
+        let frame: serde_json::Value = serde_json::from_str(line)?;
+        assert_eq!(frame["time"], 16 * (i as i64 + 1));
+        assert_eq!(frame["x"], 256.0);
+    }
+
+    Ok(())
+}
+
+/// Test that frames with stray separators are rejected at write time
+#[test]
+fn test_check_frame_rejects_stray_separators() {
+    assert!(Packer::check_frame("16|256|192|1").is_ok());
+
+    // A coordinate formatted with a decimal comma, or a wrong number of fields
+    assert!(matches!(
+        Packer::check_frame("16|256,5|192|1"),
+        Err(ReplayError::InvalidFormat(_))
+    ));
+    assert!(Packer::check_frame("16|256|1|192|1").is_err());
+    assert!(Packer::check_frame("16|256|1").is_err());
+}
+
+/// Test packing with fully customized packer options
+#[test]
+fn test_to_osr_with_options() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = create_test_replay();
+    replay.replay_data[0] = ReplayEvent::Osu(rosu_replay::ReplayEventOsu {
+        time_delta: 16,
+        x: 100.123,
+        y: 50.987,
+        keys: Key::M1,
+    });
+
+    let options = PackerOptions::new()
+        .with_preset(9)
+        .with_compression(true)
+        .with_life_bar(false)
+        .with_rng_seed(false)
+        .with_coordinate_precision(Some(1));
+    let unpacked_replay = Replay::from_bytes(&replay.to_osr(options)?)?;
+
+    assert_eq!(unpacked_replay.username, replay.username);
+    assert!(unpacked_replay.life_bar_graph.is_none());
+    assert!(unpacked_replay.rng_seed.is_none());
+    assert_eq!(unpacked_replay.replay_data.len(), replay.replay_data.len());
+    if let ReplayEvent::Osu(event) = &unpacked_replay.replay_data[0] {
+        assert_eq!(event.x, 100.1);
+        assert_eq!(event.y, 51.0);
+    } else {
+        panic!("Expected osu event");
+    }
+
+    Ok(())
+}
+
+/// Test packing in each LZMA format and reading the result back
+#[test]
+fn test_lzma_format_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let replay = create_test_replay();
+    assert_eq!(PackerOptions::default().lzma_format, LzmaFormat::Alone);
+
+    for format in [LzmaFormat::Alone, LzmaFormat::Xz] {
+        let data = replay.to_osr(PackerOptions::new().with_lzma_format(format))?;
+
+        // Detected automatically by default
+        let unpacked_replay = Replay::from_bytes(&data)?;
+        assert_eq!(unpacked_replay.replay_data, replay.replay_data);
+
+        let forced = Unpacker::new(Cursor::new(&data))
+            .with_lzma_format(Some(format))
+            .unpack()?;
+        assert_eq!(forced.replay_data, replay.replay_data);
+
+        let other_format = match format {
+            LzmaFormat::Alone => LzmaFormat::Xz,
+            LzmaFormat::Xz => LzmaFormat::Alone,
+        };
+        assert!(Unpacker::new(Cursor::new(&data))
+            .with_lzma_format(Some(other_format))
+            .unpack()
+            .is_err());
+    }
+
+    Ok(())
+}
+
+/// Test that rng seeds above i32::MAX survive a roundtrip without a sign flip
+#[test]
+fn test_large_rng_seed_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = create_test_replay();
+    replay.rng_seed = Some(3_000_000_000u32 as i32);
+
+    let unpacked_replay = Replay::from_bytes(&replay.pack()?)?;
+
+    assert_eq!(unpacked_replay.rng_seed, replay.rng_seed);
+    assert_eq!(unpacked_replay.rng_seed_u32(), Some(3_000_000_000));
+
+    Ok(())
+}
+
+/// Test that changing the beatmap hash recomputes the replay hash
+#[test]
+fn test_set_beatmap_hash_and_rehash() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = create_test_replay();
+    replay.replay_hash = replay.compute_replay_hash();
+    let original_hash = replay.replay_hash.clone();
+
+    replay.set_beatmap_hash_and_rehash("0123456789ABCDEF0123456789abcdef")?;
+
+    assert_eq!(replay.beatmap_hash, "0123456789abcdef0123456789abcdef");
+    assert_ne!(replay.replay_hash, original_hash);
+    assert_eq!(replay.replay_hash, replay.compute_replay_hash());
+    assert_eq!(replay.replay_hash.len(), 32);
+
+    // Invalid hashes are rejected without touching the replay
+    assert!(replay.set_beatmap_hash_and_rehash("not a hash").is_err());
+    assert_eq!(replay.beatmap_hash, "0123456789abcdef0123456789abcdef");
+
+    Ok(())
+}
+
+/// Test that the replay hash follows osu! stable's score checksum layout
+#[test]
+fn test_compute_replay_hash_layout() {
+    let mut replay = create_test_replay();
+    assert_eq!(replay.grade(), Grade::B);
+
+    let expected = "110p5o20o8t2aabcdef1234567890r150eFalseyTestPlayero1000000uB8True";
+    assert_eq!(
+        replay.compute_replay_hash(),
+        format!("{:x}", md5::compute(expected))
+    );
+
+    // A fail is hashed with rank F and the pass flag cleared
+    replay.life_bar_graph = Some(vec![
+        LifeBarState { time: 0, life: 1.0 },
+        LifeBarState {
+            time: 5000,
+            life: 0.0,
+        },
+    ]);
+    let failed = "110p5o20o8t2aabcdef1234567890r150eFalseyTestPlayero1000000uF8False";
+    assert_eq!(
+        replay.compute_replay_hash(),
+        format!("{:x}", md5::compute(failed))
+    );
+}
+
+/// Test encoding the game version from a client build date
+#[test]
+fn test_game_version_from_date() {
+    let mut replay = create_test_replay();
+    let date = chrono::NaiveDate::from_ymd_opt(2015, 7, 9).unwrap();
+
+    replay.set_game_version_from_date(date);
+
+    assert_eq!(replay.game_version, 20150709);
+    assert_eq!(replay.game_version_date(), Some(date));
+
+    replay.game_version = 1;
+    assert_eq!(replay.game_version_date(), None);
+}
+
+/// Test formatting the timestamp like osu! does
+#[test]
+fn test_timestamp_osu_format() {
+    use chrono::{TimeZone, Utc};
+
+    let mut replay = create_test_replay();
+    replay.timestamp = Utc.with_ymd_and_hms(2024, 3, 5, 8, 42, 7).unwrap();
+
+    assert_eq!(replay.timestamp_osu_format(), "03/05/2024 08:42:07");
+}
+
+/// Test dropping the time of day from the timestamp
+#[test]
+fn test_truncate_timestamp_to_date() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::{NaiveDate, TimeZone, Timelike, Utc};
+
+    let mut replay = create_test_replay();
+    replay.timestamp =
+        Utc.with_ymd_and_hms(2024, 3, 15, 18, 42, 7).unwrap() + chrono::Duration::milliseconds(250);
+
+    replay.truncate_timestamp_to_date();
+
+    assert_eq!(
+        replay.timestamp.date_naive(),
+        NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
+    );
+    assert_eq!(
+        (
+            replay.timestamp.hour(),
+            replay.timestamp.minute(),
+            replay.timestamp.second(),
+            replay.timestamp.nanosecond()
+        ),
+        (0, 0, 0, 0)
+    );
+
+    let unpacked_replay = Replay::from_bytes(&replay.pack()?)?;
+    assert_eq!(unpacked_replay.timestamp, replay.timestamp);
+
+    Ok(())
+}
+
+#[test]
+fn test_snap_timestamp_to_seconds() -> Result<(), Box<dyn std::error::Error>> {
+    use chrono::{TimeZone, Timelike, Utc};
+
+    let second = Utc.with_ymd_and_hms(2024, 3, 15, 18, 42, 7).unwrap();
+    let mut replay = create_test_replay();
+    replay.timestamp = second + chrono::Duration::nanoseconds(123_456_700);
+
+    let mut other = replay.clone();
+    other.timestamp = second + chrono::Duration::milliseconds(999);
+
+    replay.snap_timestamp_to_seconds();
+    other.snap_timestamp_to_seconds();
+    assert_eq!(replay.timestamp, second);
+    assert_eq!(replay.timestamp.nanosecond(), 0);
+    assert_eq!(replay.timestamp, other.timestamp);
+
+    let unpacked_replay = Replay::from_bytes(&replay.pack()?)?;
+    assert_eq!(unpacked_replay.timestamp, second);
+
+    Ok(())
+}
+
+/// Test iterating a replay's events directly
+#[test]
+fn test_replay_into_iterator() {
+    let mut replay = create_test_replay();
+
+    let mut count = 0;
+    for event in &replay {
+        assert_eq!(event.time_delta(), 16);
+        count += 1;
+    }
+    assert_eq!(count, replay.replay_data.len());
+
+    for event in &mut replay {
+        if let ReplayEvent::Osu(osu_event) = event {
+            osu_event.time_delta = 20;
+        }
+    }
+    assert!(replay.replay_data.iter().all(|e| e.time_delta() == 20));
+}
+
 /// Test different game mode events
 #[test]
 fn test_game_mode_events() {
@@ -171,45 +739,1112 @@ fn test_life_bar_data() {
     assert_eq!(life_states[2].time, 2000);
 }
 
-/// Test error handling
+/// Test generating a synthetic life bar for replays without one
 #[test]
-fn test_invalid_replay_data() {
-    // Test empty data
-    let result = Replay::from_bytes(&[]);
-    assert!(result.is_err());
+fn test_generate_flat_life_bar() {
+    let mut replay = create_test_replay();
+    replay.life_bar_graph = None;
 
-    // Test invalid data
-    let invalid_data = vec![0xFF; 10];
-    let result = Replay::from_bytes(&invalid_data);
-    assert!(result.is_err());
+    replay.generate_flat_life_bar(5);
 
-    // Test truncated data
-    let truncated_data = vec![0, 1, 2, 3];
-    let result = Replay::from_bytes(&truncated_data);
-    assert!(result.is_err());
+    let life_bar = replay.life_bar_graph.as_ref().unwrap();
+    assert_eq!(life_bar.len(), 5);
+    assert_eq!(life_bar[0].time, 0);
+    assert_eq!(life_bar[4].time, 48); // 3 frames of 16ms
+    assert!(life_bar.iter().all(|state| state.life == 1.0));
+
+    // An existing life bar is kept
+    replay.generate_flat_life_bar(2);
+    assert_eq!(replay.life_bar_graph.as_ref().unwrap().len(), 5);
+
+    // An empty life bar, as read from an empty life bar string, is filled
+    replay.life_bar_graph = Some(Vec::new());
+    replay.generate_flat_life_bar(2);
+    assert_eq!(replay.life_bar_graph.unwrap().len(), 2);
 }
 
-/// Test replay data time calculation
+/// Test appending one replay's frames after another's
 #[test]
-fn test_replay_time_calculation() {
-    let events = [
-        create_osu_event(),
-        ReplayEvent::Osu(rosu_replay::ReplayEventOsu {
-            time_delta: 50,
-            x: 100.0,
-            y: 100.0,
-            keys: Key::M1,
-        }),
-        ReplayEvent::Osu(rosu_replay::ReplayEventOsu {
-            time_delta: 33,
-            x: 200.0,
-            y: 200.0,
-            keys: Key::M2,
-        }),
-    ];
+fn test_append_replays() {
+    let mut replay = create_test_replay();
+    let other = create_test_replay();
 
-    let total_time: i32 = events.iter().map(|e| e.time_delta()).sum();
-    assert_eq!(total_time, 16 + 50 + 33); // 99ms total
+    replay.append(&other).unwrap();
+    assert_eq!(replay.replay_data.len(), 6);
+
+    let mut time = 0;
+    let times: Vec<i32> = replay
+        .replay_data
+        .iter()
+        .map(|event| {
+            time += event.time_delta();
+            time
+        })
+        .collect();
+    assert_eq!(times, vec![16, 32, 48, 64, 80, 96]);
+
+    // The appended life bar is shifted by 48ms
+    let life_bar = replay.life_bar_graph.as_ref().unwrap();
+    assert_eq!(life_bar.len(), 6);
+    assert_eq!(life_bar[3].time, 48);
+    assert_eq!(life_bar[5].time, 20048);
+
+    let mut taiko = create_test_replay();
+    taiko.mode = GameMode::Taiko;
+    assert!(matches!(
+        replay.append(&taiko),
+        Err(ReplayError::InvalidFormat(_))
+    ));
+}
+
+/// Test sorting an out-of-order life bar
+#[test]
+fn test_sort_life_bar() {
+    let mut replay = create_test_replay();
+    replay.life_bar_graph = Some(vec![
+        LifeBarState {
+            time: 2000,
+            life: 0.5,
+        },
+        LifeBarState { time: 0, life: 1.0 },
+        LifeBarState {
+            time: 1000,
+            life: 0.8,
+        },
+    ]);
+
+    replay.sort_life_bar();
+
+    let times: Vec<i32> = replay
+        .life_bar_graph
+        .unwrap()
+        .iter()
+        .map(|state| state.time)
+        .collect();
+    assert_eq!(times, vec![0, 1000, 2000]);
+}
+
+/// Test detecting stretches of the life bar without health data
+#[test]
+fn test_life_bar_gaps() {
+    let mut replay = create_test_replay();
+    replay.life_bar_graph = Some(
+        [0, 2000, 4000, 30000, 32000]
+            .into_iter()
+            .map(|time| LifeBarState { time, life: 1.0 })
+            .collect(),
+    );
+
+    assert_eq!(replay.life_bar_gaps(5000), vec![(4000, 30000)]);
+    assert!(replay.life_bar_gaps(30000).is_empty());
+
+    replay.life_bar_graph = None;
+    assert!(replay.life_bar_gaps(0).is_empty());
+}
+
+/// Test undoing the Hard Rock flip on cursor positions
+#[test]
+fn test_unmirrored_hr_frames() {
+    let mut replay = create_test_replay();
+
+    // Hidden only, so there is nothing to undo
+    assert!(replay.unmirrored_hr_frames().is_none());
+
+    replay.mods = Mod(Mod::HIDDEN.value() | Mod::HARD_ROCK.value());
+    if let ReplayEvent::Osu(event) = &mut replay.replay_data[0] {
+        event.y = 100.0;
+    }
+    let original_frames = replay.replay_data.clone();
+
+    let frames = replay.unmirrored_hr_frames().unwrap();
+    match &frames[0] {
+        ReplayEvent::Osu(event) => assert_eq!(event.y, 284.0),
+        _ => panic!("Expected osu! event"),
+    }
+
+    // The score metadata and recorded frames are kept
+    assert!(replay.mods.contains(Mod::HARD_ROCK));
+    assert_eq!(
+        replay.mods,
+        Mod(Mod::HIDDEN.value() | Mod::HARD_ROCK.value())
+    );
+    assert_eq!(replay.replay_data, original_frames);
+}
+
+/// Test masking osu!mania key bits beyond the key count
+#[test]
+fn test_clamp_mania_keys() {
+    let mut replay = create_test_replay();
+    replay.mode = GameMode::Mania;
+    replay.replay_data = vec![
+        ReplayEvent::Mania(ReplayEventMania {
+            time_delta: 16,
+            keys: KeyMania(KeyMania::K1.value() | KeyMania::K9.value()),
+        }),
+        ReplayEvent::Mania(ReplayEventMania {
+            time_delta: 16,
+            keys: KeyMania::K4,
+        }),
+    ];
+
+    assert_eq!(replay.clamp_mania_keys(4), 1);
+    assert_eq!(
+        replay.replay_data[0],
+        ReplayEvent::Mania(ReplayEventMania {
+            time_delta: 16,
+            keys: KeyMania::K1,
+        })
+    );
+    assert_eq!(replay.clamp_mania_keys(4), 0);
+}
+
+/// Test that Target Practice replays roundtrip like any other replay
+#[test]
+fn test_target_mod_roundtrip() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = create_test_replay();
+    replay.mods = Mod(Mod::TARGET.value() | Mod::HIDDEN.value());
+
+    let unpacked_replay = Replay::from_bytes(&replay.pack()?)?;
+
+    assert!(unpacked_replay.mods.contains(Mod::TARGET));
+    assert_eq!(unpacked_replay.replay_data, replay.replay_data);
+    assert_eq!(unpacked_replay.replay_id, replay.replay_id);
+
+    Ok(())
+}
+
+/// Test that the classic weights reproduce the regular accuracy
+#[test]
+fn test_accuracy_with_weights() {
+    let mut replay = create_test_replay();
+    assert_eq!(
+        replay.accuracy_with(AccuracyWeights::default()),
+        replay.accuracy()
+    );
+
+    for mode in [
+        GameMode::Std,
+        GameMode::Taiko,
+        GameMode::Catch,
+        GameMode::Mania,
+    ] {
+        replay.mode = mode;
+        let weighted = replay.accuracy_with(AccuracyWeights::for_mode(mode));
+        assert!((weighted - replay.accuracy()).abs() < 1e-9, "{:?}", mode);
+    }
+
+    // Weighing MAX above 300s lowers the accuracy of a play with 300s
+    let mut weights = AccuracyWeights::for_mode(GameMode::Mania);
+    weights.count_geki = JudgementWeight::new(305.0, 305.0);
+    for weight in [
+        &mut weights.count_300,
+        &mut weights.count_100,
+        &mut weights.count_50,
+        &mut weights.count_katu,
+        &mut weights.count_miss,
+    ] {
+        weight.denominator = 305.0;
+    }
+    assert!(replay.accuracy_with(weights) < replay.accuracy());
+}
+
+/// Test the one-line replay summary
+#[test]
+fn test_replay_summary() {
+    let mut replay = create_test_replay();
+    replay.mods = Mod(Mod::HIDDEN.value() | Mod::HARD_ROCK.value());
+
+    let summary = replay.summary();
+    assert!(summary.starts_with("TestPlayer | osu! | HDHR | 1,000,000 | "));
+    assert!(summary.contains(" | 89.03% | 150x | "), "{}", summary);
+
+    replay.mods = Mod::NO_MOD;
+    assert!(replay.summary().contains(" | NM | "));
+}
+
+/// Test that an out-of-range mode byte is preserved
+#[test]
+fn test_raw_mode_byte() -> Result<(), Box<dyn std::error::Error>> {
+    let replay = create_test_replay();
+    assert_eq!(replay.raw_mode_byte(), 0);
+
+    let mut data = replay.pack()?;
+    data[0] = 7;
+
+    assert!(matches!(
+        Replay::from_bytes(&data),
+        Err(ReplayError::InvalidFormat(_))
+    ));
+
+    let config = ParserConfig::new().with_lenient_mode(true);
+    let unpacked_replay = Replay::from_reader_with_config(Cursor::new(&data), &config)?;
+    assert_eq!(unpacked_replay.mode, GameMode::Std);
+    assert_eq!(unpacked_replay.raw_mode_byte(), 7);
+
+    Ok(())
+}
+
+/// Test detecting Cinema replays
+#[test]
+fn test_is_cinema() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = create_test_replay();
+    replay.mods = Mod::CINEMA;
+    assert!(!replay.is_cinema()); // Still has frames
+
+    replay.replay_data.clear();
+    replay.rng_seed = None;
+    assert!(replay.is_cinema());
+    assert!(Replay::from_bytes(&replay.pack()?)?.is_cinema());
+
+    replay.mods = Mod::NO_MOD;
+    assert!(!replay.is_cinema());
+
+    Ok(())
+}
+
+/// Test error handling
+#[test]
+fn test_invalid_replay_data() {
+    // Test empty data
+    let result = Replay::from_bytes(&[]);
+    assert!(result.is_err());
+
+    // Test invalid data
+    let invalid_data = vec![0xFF; 10];
+    let result = Replay::from_bytes(&invalid_data);
+    assert!(result.is_err());
+
+    // Test truncated data
+    let truncated_data = vec![0, 1, 2, 3];
+    let result = Replay::from_bytes(&truncated_data);
+    assert!(result.is_err());
+}
+
+/// Test replay data time calculation
+#[test]
+fn test_replay_time_calculation() {
+    let events = [
+        create_osu_event(),
+        ReplayEvent::Osu(rosu_replay::ReplayEventOsu {
+            time_delta: 50,
+            x: 100.0,
+            y: 100.0,
+            keys: Key::M1,
+        }),
+        ReplayEvent::Osu(rosu_replay::ReplayEventOsu {
+            time_delta: 33,
+            x: 200.0,
+            y: 200.0,
+            keys: Key::M2,
+        }),
+    ];
+
+    let total_time: i32 = events.iter().map(|e| e.time_delta()).sum();
+    assert_eq!(total_time, 16 + 50 + 33); // 99ms total
+}
+
+#[test]
+fn test_to_builder_changes_only_the_score() {
+    let original = create_test_replay();
+    let modified = original.clone().to_builder().with_score(727).build();
+
+    assert_eq!(modified.score, 727);
+
+    let mut expected = serde_json::to_value(&original).unwrap();
+    expected["score"] = serde_json::json!(727);
+    assert_eq!(serde_json::to_value(&modified).unwrap(), expected);
+}
+
+#[test]
+fn test_replay_builder_defaults() {
+    let replay = ReplayBuilder::new(GameMode::Mania)
+        .with_username("TestPlayer")
+        .with_mods(Mod::KEY4)
+        .build();
+
+    assert_eq!(replay.mode, GameMode::Mania);
+    assert_eq!(replay.username, "TestPlayer");
+    assert_eq!(replay.mods, Mod::KEY4);
+    assert_eq!(replay.score, 0);
+    assert!(replay.replay_data.is_empty());
+    assert!(replay.life_bar_graph.is_none());
+}
+
+#[test]
+fn test_mania_key_count_with_coop() {
+    let mut replay = create_test_replay();
+    replay.mode = GameMode::Mania;
+
+    replay.mods = Mod::NO_MOD;
+    assert_eq!(replay.mania_key_count(), None);
+
+    replay.mods = Mod::KEY5;
+    assert_eq!(replay.mania_key_count(), Some(5));
+
+    replay.mods = Mod(Mod::KEY5.value() | Mod::KEY_COOP.value());
+    assert_eq!(replay.mania_key_count(), Some(10));
+}
+
+#[test]
+fn test_trim_to_duration() {
+    let mut replay = create_test_replay();
+    replay.replay_data = (0..50)
+        .map(|i| {
+            ReplayEvent::Osu(ReplayEventOsu {
+                time_delta: 100,
+                x: i as f32,
+                y: 192.0,
+                keys: Key(0),
+            })
+        })
+        .collect();
+    replay.life_bar_graph = Some(
+        (0..=5)
+            .map(|i| LifeBarState {
+                time: i * 1000,
+                life: 1.0,
+            })
+            .collect(),
+    );
+
+    replay.trim_to_duration(2000);
+
+    let mut time = 0;
+    for event in &replay.replay_data {
+        time += event.time_delta();
+        assert!(time <= 2000);
+    }
+    assert_eq!(time, 2000);
+    assert_eq!(replay.replay_data.len(), 20);
+
+    let life_bar = replay.life_bar_graph.unwrap();
+    assert_eq!(life_bar.len(), 3);
+    assert!(life_bar.iter().all(|state| state.time <= 2000));
+}
+
+#[test]
+fn test_decimate_returns_retained_indices() {
+    let mut replay = create_test_replay();
+    replay.replay_data = (0..10)
+        .map(|i| {
+            ReplayEvent::Osu(ReplayEventOsu {
+                time_delta: 10,
+                x: i as f32,
+                y: 0.0,
+                keys: if i == 5 { Key::K1 } else { Key(0) },
+            })
+        })
+        .collect();
+    let original = replay.replay_data.clone();
+    let absolute_time = |events: &[ReplayEvent], index: usize| -> i32 {
+        events[..=index].iter().map(|e| e.time_delta()).sum()
+    };
+
+    let retained = replay.decimate(4);
+
+    // Every 4th frame, the key changes at 5 and 6, and the last frame
+    assert_eq!(retained, vec![0, 4, 5, 6, 8, 9]);
+    assert_eq!(replay.replay_data.len(), retained.len());
+    for (new_index, &old_index) in retained.iter().enumerate() {
+        match (&replay.replay_data[new_index], &original[old_index]) {
+            (ReplayEvent::Osu(kept), ReplayEvent::Osu(source)) => {
+                assert_eq!(kept.x, source.x);
+                assert_eq!(kept.keys, source.keys);
+            }
+            _ => panic!("Expected osu! frames"),
+        }
+        assert_eq!(
+            absolute_time(&replay.replay_data, new_index),
+            absolute_time(&original, old_index)
+        );
+    }
+}
+
+#[test]
+fn test_combo_plausible() {
+    let mut replay = create_test_replay();
+    replay.mode = GameMode::Taiko;
+    // 100 + 10 + 5 hits and 2 misses
+    replay.max_combo = 117;
+    assert!(replay.combo_plausible());
+
+    replay.max_combo = 5000;
+    assert!(!replay.combo_plausible());
+
+    // Slider ticks add combo in osu!standard, so the counts don't bound it
+    replay.mode = GameMode::Std;
+    assert!(replay.combo_plausible());
+}
+
+#[test]
+fn test_frames_guard_repairs_timing_on_drop() {
+    let mut replay = create_test_replay();
+    replay.replay_data.insert(
+        0,
+        ReplayEvent::Osu(ReplayEventOsu {
+            time_delta: -1,
+            x: 256.0,
+            y: -500.0,
+            keys: Key(0),
+        }),
+    );
+    assert_eq!(replay.frames().len(), 4);
+
+    {
+        let mut frames = replay.frames_mut_checked();
+        frames[2].set_time_delta(-40);
+        assert_eq!(frames[2].time_delta(), -40);
+    }
+
+    let deltas: Vec<i32> = replay.frames().iter().map(|e| e.time_delta()).collect();
+    assert_eq!(deltas, vec![-1, 16, 0, 16]);
+    assert_eq!(replay.repair_frame_timing(), 0);
+}
+
+#[test]
+fn test_repair_frame_timing_keeps_setup_frames() {
+    let mut replay = create_test_replay();
+    replay.replay_data = [0, -1, 1500, 16, -5]
+        .into_iter()
+        .map(|time_delta| {
+            ReplayEvent::Osu(ReplayEventOsu {
+                time_delta,
+                x: 256.0,
+                y: -500.0,
+                keys: Key(0),
+            })
+        })
+        .collect();
+
+    assert_eq!(replay.repair_frame_timing(), 1);
+    let deltas: Vec<i32> = replay.frames().iter().map(|e| e.time_delta()).collect();
+    assert_eq!(deltas, vec![0, -1, 1500, 16, 0]);
+}
+
+#[test]
+fn test_frames_guard_keeps_real_replay() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = Replay::from_path("assets/test.osr")?;
+    let original = replay.replay_data.clone();
+
+    drop(replay.frames_mut_checked());
+
+    assert_eq!(replay.replay_data, original);
+    assert_eq!(replay.repair_frame_timing(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_mod_chaining() {
+    let mods = Mod::none().with(Mod::HIDDEN).with(Mod::HARD_ROCK);
+    assert_eq!(mods, Mod::HIDDEN | Mod::HARD_ROCK);
+    assert_eq!(mods.to_string(), "HDHR");
+    assert_eq!(Mod::none(), Mod::NO_MOD);
+    assert_eq!(Mod::HIDDEN.with(Mod::HIDDEN), Mod::HIDDEN);
+}
+
+/// A writer recording the bytes written and the number of flushes
+#[derive(Default)]
+struct FlushTracker {
+    written: Vec<u8>,
+    flushes: usize,
+}
+
+impl std::io::Write for FlushTracker {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_write_to_flushes_writer() {
+    let replay = create_test_replay();
+
+    let mut tracker = FlushTracker::default();
+    let written = replay.write_to_counted(&mut tracker).unwrap();
+    assert_eq!(written, tracker.written.len());
+    assert_eq!(tracker.flushes, 1);
+
+    let mut tracker = FlushTracker::default();
+    replay.write_to(&mut tracker).unwrap();
+    assert_eq!(tracker.flushes, 1);
+
+    let mut tracker = FlushTracker::default();
+    replay.to_ndjson(&mut tracker).unwrap();
+    assert_eq!(tracker.flushes, 1);
+}
+
+#[test]
+fn test_mod_display() {
+    assert_eq!(Mod::NO_MOD.to_string(), "NM");
+    assert_eq!(
+        Mod(Mod::HIDDEN.value() | Mod::HARD_ROCK.value()).to_string(),
+        "HDHR"
+    );
+    assert_eq!(format!("{}", Mod::EASY.with(Mod::NO_FAIL)), "EZNF");
+    assert_eq!(
+        (Mod::HARD_ROCK | Mod::DOUBLE_TIME | Mod::HIDDEN).to_string(),
+        "HDDTHR"
+    );
+    assert_eq!((Mod::FLASHLIGHT | Mod::HALF_TIME).to_string(), "HTFL");
+
+    // Nightcore and Perfect always come with Double Time and Sudden Death
+    let nightcore = Mod::NIGHTCORE.with(Mod::DOUBLE_TIME);
+    assert_eq!(nightcore.to_string(), "NC");
+    assert_eq!(Mod::PERFECT.with(Mod::SUDDEN_DEATH).to_string(), "PF");
+    assert_eq!(nightcore.with(Mod::HIDDEN).to_string(), "HDNC");
+    assert_eq!(Mod::DOUBLE_TIME.to_string(), "DT");
+}
+
+#[test]
+fn test_mod_from_str() {
+    let hdhr: Mod = "HRHD".parse().unwrap();
+    assert_eq!(hdhr, Mod(Mod::HIDDEN.value() | Mod::HARD_ROCK.value()));
+    assert_eq!(hdhr.to_string(), "HDHR");
+    assert_eq!(hdhr.to_string().parse::<Mod>().unwrap(), hdhr);
+
+    assert_eq!(
+        "hddt".parse::<Mod>().unwrap(),
+        Mod::HIDDEN.with(Mod::DOUBLE_TIME)
+    );
+    let nightcore: Mod = "NC".parse().unwrap();
+    assert!(nightcore.contains(Mod::DOUBLE_TIME));
+    assert_eq!(nightcore.to_string(), "NC");
+    assert_eq!(
+        "HDSV2".parse::<Mod>().unwrap(),
+        Mod::HIDDEN.with(Mod::SCORE_V2)
+    );
+
+    assert_eq!("NM".parse::<Mod>().unwrap(), Mod::NO_MOD);
+    assert_eq!("".parse::<Mod>().unwrap(), Mod::NO_MOD);
+    assert!(matches!("HDXX".parse::<Mod>(), Err(ReplayError::Parse(_))));
+    assert!(matches!("HDH".parse::<Mod>(), Err(ReplayError::Parse(_))));
+}
+
+#[test]
+fn test_life_bar_area() {
+    let mut replay = create_test_replay();
+    let state = |time, life| LifeBarState { time, life };
+
+    // Full health for 1s, a linear drop to half over 1s, then half for 2s
+    replay.life_bar_graph = Some(vec![
+        state(0, 1.0),
+        state(1000, 1.0),
+        state(2000, 0.5),
+        state(4000, 0.5),
+    ]);
+    let average = replay.life_bar_area().unwrap();
+    assert!((average - (1000.0 + 750.0 + 1000.0) / 4000.0).abs() < 1e-9);
+
+    replay.life_bar_graph = Some(vec![state(500, 0.25)]);
+    assert_eq!(replay.life_bar_area(), Some(0.25));
+
+    replay.life_bar_graph = None;
+    assert_eq!(replay.life_bar_area(), None);
+}
+
+#[test]
+fn test_life_at() {
+    let mut replay = create_test_replay();
+    replay.life_bar_graph = Some(vec![
+        LifeBarState { time: 0, life: 1.0 },
+        LifeBarState {
+            time: 1000,
+            life: 0.0,
+        },
+    ]);
+
+    assert!((replay.life_at(500).unwrap() - 0.5).abs() < 1e-6);
+    assert!((replay.life_at(250).unwrap() - 0.75).abs() < 1e-6);
+    assert_eq!(replay.life_at(-100), Some(1.0));
+    assert_eq!(replay.life_at(1000), Some(0.0));
+    assert_eq!(replay.life_at(5000), Some(0.0));
+
+    replay.life_bar_graph = Some(vec![LifeBarState {
+        time: 200,
+        life: 0.4,
+    }]);
+    assert_eq!(replay.life_at(0), Some(0.4));
+    assert_eq!(replay.life_at(500), Some(0.4));
+
+    replay.life_bar_graph = Some(Vec::new());
+    assert_eq!(replay.life_at(500), None);
+
+    replay.life_bar_graph = None;
+    assert_eq!(replay.life_at(500), None);
+}
+
+#[test]
+fn test_has_failed() {
+    let mut replay = create_test_replay();
+    assert_eq!(replay.has_failed(), Some(false));
+
+    replay.life_bar_graph = Some(vec![
+        LifeBarState { time: 0, life: 1.0 },
+        LifeBarState {
+            time: 30000,
+            life: 0.0,
+        },
+        LifeBarState {
+            time: 60000,
+            life: 0.3,
+        },
+    ]);
+    assert_eq!(replay.has_failed(), Some(true));
+
+    // An empty life bar at the very start is not a fail
+    replay.life_bar_graph = Some(vec![
+        LifeBarState { time: 0, life: 0.0 },
+        LifeBarState {
+            time: 30000,
+            life: 0.6,
+        },
+    ]);
+    assert_eq!(replay.has_failed(), Some(false));
+
+    replay.life_bar_graph = Some(Vec::new());
+    assert_eq!(replay.has_failed(), None);
+    replay.life_bar_graph = None;
+    assert_eq!(replay.has_failed(), None);
+}
+
+#[test]
+fn test_empty_life_bar_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = create_test_replay();
+    let options = PackerOptions::default().with_compression(false);
+
+    replay.life_bar_graph = Some(Vec::new());
+    let empty = replay.to_osr(options.clone())?;
+    replay.life_bar_graph = None;
+    let null = replay.to_osr(options)?;
+
+    // The empty string is written as present with length 0, one byte longer
+    // than the null marker
+    assert_eq!(empty.len(), null.len() + 1);
+
+    replay.life_bar_graph = Some(Vec::new());
+    let parsed = Replay::from_bytes(&replay.pack()?)?;
+    assert_eq!(parsed.life_bar_graph, Some(Vec::new()));
+
+    replay.life_bar_graph = None;
+    let parsed = Replay::from_bytes(&replay.pack()?)?;
+    assert_eq!(parsed.life_bar_graph, None);
+    assert!(replay.verify_round_trip()?);
+
+    replay.life_bar_graph = Some(Vec::new());
+    assert!(replay.verify_round_trip()?);
+
+    Ok(())
+}
+
+#[test]
+fn test_max_score_round_trip() {
+    let mut replay = create_test_replay();
+    replay.score = u32::MAX;
+
+    let parsed = Replay::from_bytes(&replay.pack().unwrap()).unwrap();
+    assert_eq!(parsed.score, u32::MAX);
+    assert_eq!(parsed.score_i64(), 4_294_967_295);
+    assert_eq!(parsed.score_i64() + parsed.score_i64(), 2 * u32::MAX as i64);
+}
+
+#[test]
+fn test_mod_iter() {
+    assert_eq!(Mod::NO_MOD.iter().count(), 0);
+
+    let mods = Mod::HARD_ROCK
+        .with(Mod::HIDDEN)
+        .with(Mod::NIGHTCORE)
+        .with(Mod::DOUBLE_TIME);
+    let listed: Vec<String> = mods.iter().map(|m| m.to_string()).collect();
+    assert_eq!(listed, vec!["HD", "HR", "DT", "NC"]);
+
+    // Bit 31 has no mod but is still yielded
+    let reserved = Mod(1 << 31).with(Mod::NO_FAIL);
+    assert_eq!(
+        reserved.iter().collect::<Vec<_>>(),
+        vec![Mod::NO_FAIL, Mod(1 << 31)]
+    );
+}
+
+#[test]
+fn test_to_statistics() {
+    let replay = create_test_replay();
+    let statistics = replay.to_statistics();
+
+    assert_eq!(
+        statistics,
+        Statistics {
+            great: 100,
+            ok: 10,
+            meh: 5,
+            miss: 2,
+            perfect: 20,
+            good: 8,
+        }
+    );
+}
+
+#[test]
+fn test_flag_operators() {
+    let mut mods = Mod::HIDDEN | Mod::HARD_ROCK;
+    assert_eq!(mods.value(), 24);
+    assert_eq!(mods & Mod::HIDDEN, Mod::HIDDEN);
+
+    mods &= !Mod::HIDDEN;
+    assert_eq!(mods, Mod::HARD_ROCK);
+    mods |= Mod::DOUBLE_TIME;
+    assert_eq!(mods.to_string(), "DTHR");
+
+    let mut keys = Key::M1 | Key::K1;
+    keys &= !Key::K1;
+    assert_eq!(keys, Key::M1);
+    assert_eq!(KeyTaiko::LEFT_DON | KeyTaiko::RIGHT_KAT, KeyTaiko(0b1001));
+    assert_eq!(
+        (KeyMania::K1 | KeyMania::K2 | KeyMania::K3) & !KeyMania::K2,
+        KeyMania(0b101)
+    );
+}
+
+#[test]
+fn test_is_empty_play() {
+    let mut replay = create_test_replay();
+    replay.replay_data[1] = ReplayEvent::Osu(ReplayEventOsu {
+        time_delta: 16,
+        x: 256.0,
+        y: 192.0,
+        keys: Key::K1,
+    });
+    assert!(!replay.is_empty_play());
+
+    let empty = replay
+        .clone()
+        .to_builder()
+        .with_count_300(0)
+        .with_count_100(0)
+        .with_count_50(0)
+        .with_count_geki(0)
+        .with_count_katu(0)
+        .with_count_miss(0)
+        .with_replay_data(vec![
+            ReplayEvent::Osu(ReplayEventOsu {
+                time_delta: -1,
+                x: 256.0,
+                y: -500.0,
+                keys: Key(0),
+            });
+            3
+        ])
+        .build();
+    assert!(empty.is_empty_play());
+
+    // Zero counts alone aren't enough if keys were pressed
+    let mut pressed = empty.clone();
+    pressed.replay_data = replay.replay_data.clone();
+    assert!(!pressed.is_empty_play());
+}
+
+#[test]
+fn test_mod_speed_multiplier() {
+    assert_eq!(Mod::NO_MOD.speed_multiplier(), 1.0);
+    assert_eq!((Mod::HIDDEN | Mod::HARD_ROCK).speed_multiplier(), 1.0);
+    assert_eq!(Mod::DOUBLE_TIME.speed_multiplier(), 1.5);
+    assert_eq!(Mod::NIGHTCORE.speed_multiplier(), 1.5);
+    assert_eq!((Mod::NIGHTCORE | Mod::DOUBLE_TIME).speed_multiplier(), 1.5);
+    assert_eq!(Mod::HALF_TIME.speed_multiplier(), 0.75);
+    assert_eq!((Mod::HALF_TIME | Mod::DOUBLE_TIME).speed_multiplier(), 1.5);
+
+    // 300ms of beatmap time pass in 200ms with Double Time
+    assert_eq!(300.0 / Mod::DOUBLE_TIME.speed_multiplier(), 200.0);
+}
+
+#[test]
+fn test_game_mode_try_from_u8() {
+    assert_eq!(GameMode::try_from_u8(3).unwrap(), GameMode::Mania);
+    assert!(matches!(
+        GameMode::try_from_u8(7),
+        Err(ReplayError::InvalidFormat(_))
+    ));
+    // The lossy conversion is kept for compatibility
+    assert_eq!(GameMode::from(7), GameMode::Std);
+}
+
+#[test]
+fn test_game_mode_display_and_from_str() {
+    for mode in [
+        GameMode::Std,
+        GameMode::Taiko,
+        GameMode::Catch,
+        GameMode::Mania,
+    ] {
+        assert_eq!(mode.to_string().parse::<GameMode>().unwrap(), mode);
+        assert_eq!((mode as u8).to_string().parse::<GameMode>().unwrap(), mode);
+    }
+
+    assert_eq!(GameMode::Catch.to_string(), "fruits");
+    assert_eq!("std".parse::<GameMode>().unwrap(), GameMode::Std);
+    assert_eq!("CTB".parse::<GameMode>().unwrap(), GameMode::Catch);
+    assert_eq!("catch".parse::<GameMode>().unwrap(), GameMode::Catch);
+    assert!(matches!(
+        "4".parse::<GameMode>(),
+        Err(ReplayError::Parse(_))
+    ));
+    assert!(matches!(
+        "piano".parse::<GameMode>(),
+        Err(ReplayError::Parse(_))
+    ));
+}
+
+#[test]
+fn test_always_emit_seed_frame() {
+    let mut replay = create_test_replay();
+    replay.rng_seed = None;
+    let placeholder = b"-12345|0|0|0,";
+    let contains = |data: &[u8]| data.windows(placeholder.len()).any(|w| w == placeholder);
+
+    let default = Packer::new().pack_uncompressed(&replay).unwrap();
+    assert!(!contains(&default));
+
+    let options = PackerOptions::new().with_always_emit_seed_frame(true);
+    let packer = Packer::with_options(options);
+    assert!(contains(&packer.pack_uncompressed(&replay).unwrap()));
+    let parsed = Replay::from_bytes(&packer.pack(&replay).unwrap()).unwrap();
+    assert_eq!(parsed.rng_seed, Some(0));
+
+    // A real seed is written as is
+    replay.rng_seed = Some(67890);
+    assert!(!contains(&packer.pack_uncompressed(&replay).unwrap()));
+    let parsed = Replay::from_bytes(&packer.pack(&replay).unwrap()).unwrap();
+    assert_eq!(parsed.rng_seed, Some(67890));
+}
+
+#[test]
+fn test_accuracy_per_mode() {
+    // 100 300s, 10 100s, 5 50s, 20 gekis, 8 katus and 2 misses
+    let mut replay = create_test_replay();
+    let expected = [
+        // (300 * 100 + 100 * 10 + 50 * 5) / (300 * 117)
+        (GameMode::Std, 31_250.0 / 35_100.0),
+        // (100 + 0.5 * 10) / 117
+        (GameMode::Taiko, 105.0 / 117.0),
+        // 115 caught out of 115 + 2 misses + 8 missed droplets
+        (GameMode::Catch, 115.0 / 125.0),
+        // (300 * (20 + 100) + 200 * 8 + 100 * 10 + 50 * 5) / (300 * 145)
+        (GameMode::Mania, 38_850.0 / 43_500.0),
+    ];
+
+    for (mode, fraction) in expected {
+        replay.mode = mode;
+        let accuracy = replay.accuracy();
+        assert!(
+            (accuracy - fraction * 100.0).abs() < 1e-9,
+            "{:?}: {}",
+            mode,
+            accuracy
+        );
+        assert!((0.0..=100.0).contains(&accuracy));
+    }
+
+    // A perfect play and an empty play both read 100%
+    replay.mode = GameMode::Std;
+    replay.count_100 = 0;
+    replay.count_50 = 0;
+    replay.count_miss = 0;
+    assert_eq!(replay.accuracy(), 100.0);
+
+    replay.count_300 = 0;
+    assert_eq!(replay.accuracy(), 100.0);
+}
+
+#[test]
+fn test_frames_per_kb() {
+    let frame = |x: f32, y: f32| {
+        ReplayEvent::Osu(ReplayEventOsu {
+            time_delta: 16,
+            x,
+            y,
+            keys: Key(0),
+        })
+    };
+
+    let mut dense = create_test_replay();
+    dense.replay_data = vec![frame(256.0, 192.0); 2000];
+
+    // Pseudo-random positions compress far worse than a resting cursor
+    let mut sparse = create_test_replay();
+    let mut state = 12345u32;
+    sparse.replay_data = (0..2000)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            frame(
+                (state % 51_200) as f32 / 100.0,
+                (state >> 16) as f32 / 171.0,
+            )
+        })
+        .collect();
+
+    let dense_ratio = dense.frames_per_kb().unwrap();
+    let sparse_ratio = sparse.frames_per_kb().unwrap();
+    assert!(
+        dense_ratio > sparse_ratio * 2.0,
+        "{} vs {}",
+        dense_ratio,
+        sparse_ratio
+    );
+    assert!(sparse_ratio > 0.0);
+}
+
+#[test]
+fn test_grade() {
+    let mut replay = create_test_replay();
+    let set_counts = |replay: &mut Replay, counts: [u16; 6]| {
+        replay.count_300 = counts[0];
+        replay.count_100 = counts[1];
+        replay.count_50 = counts[2];
+        replay.count_geki = counts[3];
+        replay.count_katu = counts[4];
+        replay.count_miss = counts[5];
+    };
+
+    // Full combo with only 300s and Hidden
+    set_counts(&mut replay, [500, 0, 0, 100, 0, 0]);
+    replay.mods = Mod::HIDDEN;
+    assert_eq!(replay.grade(), Grade::SSH);
+    replay.mods = Mod::NO_MOD;
+    assert_eq!(replay.grade(), Grade::SS);
+
+    set_counts(&mut replay, [95, 5, 0, 0, 0, 0]);
+    assert_eq!(replay.grade(), Grade::S);
+    replay.mods = Mod::FLASHLIGHT;
+    assert_eq!(replay.grade(), Grade::SH);
+    replay.mods = Mod::NO_MOD;
+
+    // A single miss drops an otherwise S play to A
+    set_counts(&mut replay, [95, 4, 0, 0, 0, 1]);
+    assert_eq!(replay.grade(), Grade::A);
+    set_counts(&mut replay, [75, 25, 0, 0, 0, 0]);
+    assert_eq!(replay.grade(), Grade::B);
+    set_counts(&mut replay, [65, 30, 0, 0, 0, 5]);
+    assert_eq!(replay.grade(), Grade::C);
+    set_counts(&mut replay, [50, 40, 0, 0, 0, 10]);
+    assert_eq!(replay.grade(), Grade::D);
+
+    // osu!mania grades by accuracy, with Fade In counting as silver
+    replay.mode = GameMode::Mania;
+    set_counts(&mut replay, [50, 0, 0, 50, 0, 0]);
+    replay.mods = Mod::FADE_IN;
+    assert_eq!(replay.grade(), Grade::SSH);
+    replay.mods = Mod::NO_MOD;
+    set_counts(&mut replay, [50, 4, 0, 40, 6, 0]);
+    assert_eq!(replay.grade(), Grade::S);
+
+    set_counts(&mut replay, [0, 0, 0, 0, 0, 0]);
+    assert_eq!(replay.grade(), Grade::D);
+}
+
+#[test]
+fn test_unused_keys() {
+    let mut replay = create_test_replay();
+    replay.replay_data = vec![
+        ReplayEvent::Osu(ReplayEventOsu {
+            time_delta: 16,
+            x: 256.0,
+            y: 192.0,
+            keys: Key::K1,
+        }),
+        ReplayEvent::Osu(ReplayEventOsu {
+            time_delta: 16,
+            x: 256.0,
+            y: 192.0,
+            keys: Key(0),
+        }),
+    ];
+    assert_eq!(replay.unused_keys(), (Key::K2 | Key::M1 | Key::M2).value());
+
+    // Unused osu!mania lanes are reported against the key mod's lane count
+    replay.mode = GameMode::Mania;
+    replay.mods = Mod::KEY4;
+    replay.replay_data = vec![ReplayEvent::Mania(ReplayEventMania {
+        time_delta: 16,
+        keys: KeyMania::K1 | KeyMania::K3,
+    })];
+    assert_eq!(replay.unused_keys(), (KeyMania::K2 | KeyMania::K4).value());
+}
+
+#[test]
+fn test_events_with_time() {
+    let mut replay = create_test_replay();
+    replay.replay_data = [16, -1, 16]
+        .into_iter()
+        .map(|time_delta| {
+            ReplayEvent::Osu(ReplayEventOsu {
+                time_delta,
+                x: 256.0,
+                y: 192.0,
+                keys: Key(0),
+            })
+        })
+        .collect();
+
+    let times: Vec<i64> = replay.events_with_time().map(|(time, _)| time).collect();
+    assert_eq!(times, vec![16, 15, 31]);
+
+    replay.replay_data.clear();
+    assert_eq!(replay.events_with_time().count(), 0);
+}
+
+#[test]
+fn test_unknown_mod_bits_round_trip() {
+    let mods = Mod::HIDDEN.with(Mod::MIRROR).with(Mod(1 << 31));
+    assert_eq!(mods.known_bits(), (Mod::HIDDEN | Mod::MIRROR).value());
+    assert_eq!(mods.unknown_bits(), 1 << 31);
+    assert_eq!(Mod::HIDDEN.unknown_bits(), 0);
+
+    let mut replay = create_test_replay();
+    replay.mods = mods;
+
+    let parsed = Replay::from_bytes(&replay.pack().unwrap()).unwrap();
+    assert_eq!(parsed.mods, mods);
+    assert_eq!(parsed.mods.unknown_bits(), 1 << 31);
+}
+
+#[test]
+fn test_pp_context_json() {
+    let mut replay = create_test_replay();
+    replay.mods = Mod::HIDDEN | Mod::NIGHTCORE | Mod::DOUBLE_TIME;
+
+    let context: serde_json::Value = serde_json::from_str(&replay.pp_context_json()).unwrap();
+    let object = context.as_object().unwrap();
+
+    let mut keys: Vec<&str> = object.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    assert_eq!(
+        keys,
+        vec![
+            "accuracy",
+            "max_combo",
+            "misses",
+            "mode",
+            "mods",
+            "n100",
+            "n300",
+            "n50",
+            "n_geki",
+            "n_katu"
+        ]
+    );
+
+    assert_eq!(context["mode"], "osu");
+    assert_eq!(context["mods"], serde_json::json!(["HD", "NC"]));
+    assert_eq!(context["n300"], 100);
+    assert_eq!(context["misses"], 2);
+    assert_eq!(context["max_combo"], 150);
+    assert!((context["accuracy"].as_f64().unwrap() - replay.accuracy()).abs() < 1e-9);
 }
 
 // Helper functions for creating test data
@@ -246,6 +1881,10 @@ fn create_test_replay() -> Replay {
         replay_data: vec![create_osu_event(), create_osu_event(), create_osu_event()],
         replay_id: 12345,
         rng_seed: Some(67890),
+        target_practice: None,
+        mania_settings: None,
+        lazer_data: None,
+        mode_byte: None,
     }
 }
 
@@ -1,5 +1,8 @@
-use rosu_replay::unpacker::Unpacker;
-use rosu_replay::{GameMode, ReplayEvent};
+use rosu_replay::unpacker::{Unpacker, LAZER_GAME_VERSION};
+use rosu_replay::{
+    parse_replay_data_strict, GameMode, LifeBarState, ManiaSettings, Mod, Packer, ParseReport,
+    ParseStage, ParserConfig, Replay, ReplayBuilder, ReplayError, ReplayEvent,
+};
 use std::io::Cursor;
 
 /// Test parsing replay data from string format
@@ -154,6 +157,21 @@ fn test_parse_replay_data_trailing_comma() -> Result<(), Box<dyn std::error::Err
     Ok(())
 }
 
+/// Test that strict parsing rejects frames without 4 parts
+#[test]
+fn test_strict_parsing_rejects_malformed_frame() -> Result<(), Box<dyn std::error::Error>> {
+    let replay_data = "16|256.0|192.0|1,32|300.0|2,48|400.0|250.0|0,";
+
+    // Lenient parsing skips the 3-part frame
+    let (events, _) = Unpacker::<Cursor<&[u8]>>::parse_replay_data(replay_data, GameMode::Std)?;
+    assert_eq!(events.len(), 2);
+
+    let result = parse_replay_data_strict(replay_data.as_bytes(), false, true, GameMode::Std);
+    assert!(matches!(result, Err(ReplayError::InvalidFormat(_))));
+
+    Ok(())
+}
+
 /// Test string parsing utilities
 #[test]
 fn test_string_parsing() -> Result<(), Box<dyn std::error::Error>> {
@@ -212,3 +230,386 @@ fn test_life_bar_parsing() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Test that lenient life bar parsing skips malformed states
+#[test]
+fn test_lenient_life_bar_parsing() -> Result<(), Box<dyn std::error::Error>> {
+    let life_bar_data = "1000|1.0,2000;0.8,3000|0.6,";
+
+    let mut data = vec![0x0b];
+    data.push(life_bar_data.len() as u8);
+    data.extend_from_slice(life_bar_data.as_bytes());
+
+    // Strict by default
+    let mut unpacker = Unpacker::new(Cursor::new(data.clone()));
+    assert!(unpacker.unpack_life_bar().is_err());
+
+    let mut unpacker = Unpacker::new(Cursor::new(data)).with_lenient_life_bar(true);
+    let life_bar = unpacker.unpack_life_bar()?.unwrap();
+
+    assert_eq!(life_bar.len(), 2);
+    assert_eq!(life_bar[0].time, 1000);
+    assert_eq!(life_bar[1].time, 3000);
+    assert_eq!(unpacker.skipped_life_bar_states(), 1);
+
+    Ok(())
+}
+
+/// Test that the skipped life bar states are reported with the parsed replay
+#[test]
+fn test_lenient_life_bar_report() -> Result<(), Box<dyn std::error::Error>> {
+    let replay = ReplayBuilder::new(GameMode::Std)
+        .with_life_bar_graph(Some(vec![
+            LifeBarState {
+                time: 1000,
+                life: 1.0,
+            },
+            LifeBarState {
+                time: 3000,
+                life: 0.5,
+            },
+        ]))
+        .build();
+    let packed = replay.pack()?;
+
+    // Swap the life bar string for one with a malformed state in the middle
+    let valid = b"1000|1,3000|0.5,";
+    let malformed = b"1000|1,2000;0.8,3000|0.5,";
+    let start = packed
+        .windows(valid.len())
+        .position(|window| window == valid)
+        .unwrap();
+    assert_eq!(packed[start - 1] as usize, valid.len());
+    let mut data = packed[..start - 1].to_vec();
+    data.push(malformed.len() as u8);
+    data.extend_from_slice(malformed);
+    data.extend_from_slice(&packed[start + valid.len()..]);
+
+    assert!(Replay::from_reader_with_report(Cursor::new(&data), &ParserConfig::default()).is_err());
+
+    let config = ParserConfig::new().with_lenient_life_bar(true);
+    let (parsed, report) = Replay::from_reader_with_report(Cursor::new(&data), &config)?;
+    assert_eq!(parsed.life_bar_graph.map(|states| states.len()), Some(2));
+    assert_eq!(
+        report,
+        ParseReport {
+            skipped_life_bar_states: 1
+        }
+    );
+
+    let (_, report) = Replay::from_reader_with_report(Cursor::new(&packed), &config)?;
+    assert_eq!(report, ParseReport::default());
+
+    Ok(())
+}
+
+/// Test reading osu!mania settings from a lazer extra-data block
+#[test]
+fn test_lazer_mania_settings() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = Replay::from_path("assets/test.osr")?;
+    assert_eq!(replay.mode, GameMode::Mania);
+    assert!(replay.mania_settings.is_none());
+    replay.game_version = LAZER_GAME_VERSION;
+
+    let extra_data = liblzma::encode_all(
+        br#"{"online_id":1,"mania_settings":{"scroll_speed":25.5}}"#.as_slice(),
+        6,
+    )?;
+    let mut data = replay.pack()?;
+    data.extend_from_slice(&(extra_data.len() as u32).to_le_bytes());
+    data.extend_from_slice(&extra_data);
+
+    let lazer_replay = Replay::from_bytes(&data)?;
+    assert_eq!(
+        lazer_replay.mania_settings,
+        Some(ManiaSettings { scroll_speed: 25.5 })
+    );
+    assert_eq!(lazer_replay.replay_data, replay.replay_data);
+
+    // The settings and the rest of the block survive a roundtrip
+    let repacked = Replay::from_bytes(&lazer_replay.pack()?)?;
+    assert_eq!(repacked.mania_settings, lazer_replay.mania_settings);
+    assert_eq!(repacked.lazer_data, lazer_replay.lazer_data);
+    assert_eq!(repacked.lazer_data.unwrap()["online_id"], 1);
+
+    Ok(())
+}
+
+/// Test that bytes after the replay id of a stable replay are ignored
+#[test]
+fn test_stable_replay_trailing_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = std::fs::read("assets/test.osr")?;
+    data.extend_from_slice(&[0; 6]);
+
+    let replay = Replay::from_bytes(&data)?;
+    assert!(replay.game_version < LAZER_GAME_VERSION);
+    assert!(replay.lazer_data.is_none());
+    assert!(replay.mania_settings.is_none());
+
+    Ok(())
+}
+
+/// Test that a lazer data block that can't be decoded is only an error in strict mode
+#[test]
+fn test_undecodable_lazer_data() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = Replay::from_path("assets/test.osr")?;
+    replay.game_version = LAZER_GAME_VERSION;
+    let mut data = replay.pack()?;
+    data.extend_from_slice(&[0; 6]);
+
+    let parsed = Replay::from_bytes(&data)?;
+    assert!(parsed.lazer_data.is_none());
+    assert_eq!(parsed.replay_data, replay.replay_data);
+
+    let config = ParserConfig::new().with_strict_lazer_data(true);
+    assert!(Replay::from_reader_with_config(Cursor::new(&data), &config).is_err());
+
+    Ok(())
+}
+
+/// Test the stages reported while parsing a replay
+#[test]
+fn test_parse_progress_stages() -> Result<(), Box<dyn std::error::Error>> {
+    let file = std::fs::File::open("assets/test.osr")?;
+    let mut stages = Vec::new();
+    let replay = Replay::from_reader_with_progress(file, |stage| stages.push(stage))?;
+
+    assert_eq!(
+        stages[..3],
+        [
+            ParseStage::Header,
+            ParseStage::LifeBar,
+            ParseStage::Decompressing
+        ]
+    );
+    assert_eq!(stages.last(), Some(&ParseStage::Done));
+
+    let frame_progress: Vec<(usize, usize)> = stages[3..stages.len() - 1]
+        .iter()
+        .map(|stage| match *stage {
+            ParseStage::ParsingFrames { done, total } => (done, total),
+            other => panic!("unexpected stage {:?}", other),
+        })
+        .collect();
+    let &(done, total) = frame_progress.last().unwrap();
+    assert_eq!(done, total);
+    assert!(total >= replay.replay_data.len());
+    assert!(frame_progress.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+    Ok(())
+}
+
+/// Test parsing the same data under different parser configs
+#[test]
+fn test_parser_config() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = Replay::from_path("assets/test.osr")?;
+    replay.game_version = 1;
+    let data = replay.pack()?;
+
+    let lenient = Replay::from_reader_with_config(data.as_slice(), &ParserConfig::default())?;
+    assert_eq!(lenient.game_version, 1);
+
+    let strict = ParserConfig::new()
+        .with_strict_version(true)
+        .with_strict_frames(true);
+    assert!(matches!(
+        Replay::from_reader_with_config(data.as_slice(), &strict),
+        Err(ReplayError::UnsupportedVersion(1))
+    ));
+
+    let unpacker = Unpacker::with_config(data.as_slice(), strict.clone());
+    assert_eq!(unpacker.config(), &strict);
+
+    Ok(())
+}
+
+/// Test parsing frames with non-finite coordinates
+#[test]
+fn test_non_finite_coordinates() -> Result<(), Box<dyn std::error::Error>> {
+    let replay_data = "16|inf|192|1,16|100|NaN|0";
+
+    assert!(matches!(
+        Unpacker::<Cursor<&[u8]>>::parse_replay_data_with(replay_data, GameMode::Std, true),
+        Err(ReplayError::Parse(_))
+    ));
+    assert!(matches!(
+        Unpacker::<Cursor<&[u8]>>::parse_replay_data_with("16|-inf|0|1", GameMode::Catch, true),
+        Err(ReplayError::Parse(_))
+    ));
+
+    // Replaced with 0 by default
+    let (events, _) = Unpacker::<Cursor<&[u8]>>::parse_replay_data(replay_data, GameMode::Std)?;
+    match (&events[0], &events[1]) {
+        (ReplayEvent::Osu(first), ReplayEvent::Osu(second)) => {
+            assert_eq!((first.x, first.y), (0.0, 192.0));
+            assert_eq!((second.x, second.y), (100.0, 0.0));
+        }
+        _ => panic!("Expected osu! events"),
+    }
+
+    Ok(())
+}
+
+/// Test that quick_info matches a full parse and stops before the life bar
+#[test]
+fn test_quick_info_matches_full_parse() -> Result<(), Box<dyn std::error::Error>> {
+    let data = std::fs::read("assets/test.osr")?;
+    let replay = Replay::from_bytes(&data)?;
+    let info = Replay::quick_info(&data)?;
+
+    assert_eq!(info.mode, replay.mode);
+    assert_eq!(info.username, replay.username);
+    assert_eq!(info.beatmap_hash, replay.beatmap_hash);
+    assert_eq!(info.score, replay.score);
+    assert_eq!(info.max_combo, replay.max_combo);
+    assert_eq!(info.mods, replay.mods);
+
+    // Cut the data right after the mods: a full parse fails, quick_info doesn't
+    // Every string in this replay is present (0x0b) and shorter than 128 bytes
+    let string_len = |s: &str| 2 + s.len();
+    let header_len = 1
+        + 4
+        + string_len(&replay.beatmap_hash)
+        + string_len(&replay.username)
+        + string_len(&replay.replay_hash)
+        + 6 * 2
+        + 4
+        + 2
+        + 1
+        + 4;
+    let header = &data[..header_len];
+
+    assert!(Replay::from_bytes(header).is_err());
+    assert_eq!(Replay::quick_info(header)?, info);
+    Ok(())
+}
+
+/// Test normalizing NC-only mods while parsing
+#[test]
+fn test_normalize_mods_on_parse() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = Replay::from_path("assets/test.osr")?;
+    replay.mods = Mod::NIGHTCORE;
+    let data = replay.pack()?;
+
+    let parsed = Replay::from_bytes(&data)?;
+    assert_eq!(parsed.mods, Mod::NIGHTCORE);
+
+    let config = ParserConfig::new().with_normalize_mods_on_parse(true);
+    let parsed = Replay::from_reader_with_config(Cursor::new(&data), &config)?;
+    assert!(parsed.mods.contains(Mod::NIGHTCORE));
+    assert!(parsed.mods.contains(Mod::DOUBLE_TIME));
+    Ok(())
+}
+
+/// Reads the raw frame block of `.osr` data, decompressing it if needed
+fn raw_frame_block(data: &[u8], compressed: bool) -> Result<Vec<u8>, ReplayError> {
+    let mut unpacker = Unpacker::new(Cursor::new(data));
+    unpacker.unpack_byte()?;
+    unpacker.unpack_int()?;
+    for _ in 0..3 {
+        unpacker.unpack_string()?;
+    }
+    for _ in 0..7 {
+        unpacker.unpack_short()?;
+    }
+    unpacker.unpack_int()?;
+    unpacker.unpack_byte()?;
+    unpacker.unpack_int()?;
+    unpacker.unpack_string()?;
+    unpacker.unpack_long()?;
+
+    let length = unpacker.unpack_int()?;
+    let block = (0..length)
+        .map(|_| unpacker.unpack_byte())
+        .collect::<Result<Vec<u8>, _>>()?;
+
+    if compressed {
+        let stream = liblzma::stream::Stream::new_lzma_decoder(u64::MAX)?;
+        let mut decoder = liblzma::read::XzDecoder::new_stream(block.as_slice(), stream);
+        let mut decompressed = Vec::new();
+        std::io::Read::read_to_end(&mut decoder, &mut decompressed)?;
+        Ok(decompressed)
+    } else {
+        Ok(block)
+    }
+}
+
+/// Test that packed frame blocks end exactly like osu!'s
+#[test]
+fn test_frame_block_terminator_matches_osu() -> Result<(), Box<dyn std::error::Error>> {
+    let data = std::fs::read("assets/test.osr")?;
+    let replay = Replay::from_bytes(&data)?;
+    let seed = replay.rng_seed.ok_or("test replay has no seed frame")? as u32;
+
+    let original = String::from_utf8(raw_frame_block(&data, true)?)?;
+    let packed = Packer::new().pack_uncompressed(&replay)?;
+    let packed = String::from_utf8(raw_frame_block(&packed, false)?)?;
+
+    let terminator = format!(",-12345|0|0|{},", seed);
+    assert!(original.ends_with(&terminator));
+    assert!(packed.ends_with(&terminator));
+    assert!(!packed.ends_with(",,"));
+    Ok(())
+}
+
+/// Test reading the target practice accuracy that precedes the lazer block
+#[test]
+fn test_target_practice_block() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = Replay::from_path("assets/test.osr")?;
+    replay.game_version = LAZER_GAME_VERSION;
+    replay.mods = Mod::TARGET;
+    replay.target_practice = Some(0.875);
+    replay.mania_settings = Some(ManiaSettings { scroll_speed: 24.0 });
+
+    let parsed = Replay::from_bytes(&replay.pack()?)?;
+    assert_eq!(parsed.target_practice, Some(0.875));
+    assert_eq!(parsed.mania_settings, replay.mania_settings);
+    assert!(replay.verify_round_trip()?);
+
+    // Without the mod, the bytes after the replay id are the lazer block
+    replay.mods = Mod::NO_MOD;
+    replay.target_practice = None;
+    let parsed = Replay::from_bytes(&replay.pack()?)?;
+    assert_eq!(parsed.target_practice, None);
+    assert_eq!(parsed.mania_settings, replay.mania_settings);
+    Ok(())
+}
+
+/// Test that a plain replay has no target practice accuracy
+#[test]
+fn test_plain_replay_has_no_target_practice() -> Result<(), Box<dyn std::error::Error>> {
+    let replay = Replay::from_path("assets/test.osr")?;
+    assert!(!replay.mods.contains(Mod::TARGET));
+    assert_eq!(replay.target_practice, None);
+
+    // A target practice replay that ends at the replay id has no value either
+    let mut target = replay.clone();
+    target.mods = Mod::TARGET;
+    let mut data = target.pack()?;
+    data.truncate(data.len() - 8);
+    assert_eq!(Replay::from_bytes(&data)?.target_practice, None);
+    Ok(())
+}
+
+/// Test reading a 4-byte trailing replay id from a seekable reader
+#[test]
+fn test_from_reader_seek_short_replay_id() -> Result<(), Box<dyn std::error::Error>> {
+    let mut replay = Replay::from_path("assets/test.osr")?;
+    replay.replay_id = 123_456;
+
+    let mut data = replay.pack()?;
+    data.truncate(data.len() - 8);
+    data.extend_from_slice(&123_456u32.to_le_bytes());
+
+    let parsed = Replay::from_reader_seek(Cursor::new(&data))?;
+    assert_eq!(parsed.replay_id, 123_456);
+    assert_eq!(parsed.replay_data.len(), replay.replay_data.len());
+
+    // 8-byte ids read the same as without seeking
+    let data = replay.pack()?;
+    assert_eq!(
+        Replay::from_reader_seek(Cursor::new(&data))?.replay_id,
+        123_456
+    );
+    Ok(())
+}
@@ -246,5 +246,9 @@ fn create_minimal_test_replay() -> Replay {
         ],
         replay_id: 123456,
         rng_seed: None,
+        target_practice: None,
+        mania_settings: None,
+        lazer_data: None,
+        mode_byte: None,
     }
 }